@@ -1,82 +1,276 @@
-use crate::structs::{FabConfig, WhoAmIResponse};
-use crate::WHO_AM_I;
+use crate::errors::{InvalidArgument, NotFound};
+use crate::structs::{FabConfig, UserResponse};
 use anyhow::{anyhow, Error};
-use reqwest::RequestBuilder;
-use serde::Deserialize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirmation, Select};
+use fab::conduit::{ConduitClient, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::{read_to_string, File};
-use std::{fs, io};
+use std::{env, fs, io};
+use tokio::runtime::Runtime;
+
+/// The profile that is set up on the first run, and that configurations from
+/// before profiles existed are moved to.
+const DEFAULT_PROFILE: &str = "default";
+
+/// The configuration stored in `~/.fab/config.json`: a profile for every instance
+/// or account, keyed by its name.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Profiles {
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// The instance, API token and user of a profile.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub hosted_instance: String,
+    pub api_token: String,
+    pub phid: String,
+}
+
+/// The formats `~/.fab/config.json` was written in.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredConfig {
+    Profiles(Profiles),
+    /// A single instance, from before profiles existed.
+    Single(Profile),
+}
+
+/// The parts of Arcanist's `~/.arcrc` that hold its credentials.
+#[derive(Deserialize, Debug, Default)]
+struct Arcrc {
+    #[serde(default)]
+    hosts: BTreeMap<String, ArcrcHost>,
+    #[serde(default)]
+    config: ArcrcConfig,
+}
+
+#[derive(Deserialize, Debug)]
+struct ArcrcHost {
+    token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ArcrcConfig {
+    default: Option<String>,
+}
 
-pub fn init() -> Result<FabConfig, Error> {
-    let existing_config = read_config();
+/// Returned when fab would have to ask for the instance or an API token, but
+/// runs with `--non-interactive`.
+#[derive(Debug)]
+pub struct SetupRequired;
+
+impl fmt::Display for SetupRequired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Fab isn't set up yet. Run it once without --non-interactive to set it up"
+        )
+    }
+}
 
-    match existing_config {
-        Ok(config) => Result::Ok(config),
-        Err(_) => {
-            println!(
-                " _    _      _                            _         ______    _
+impl std::error::Error for SetupRequired {}
+
+/// Reads the configuration, or sets fab up if there is none. The profile is the
+/// one given with `--profile`, then `FAB_PROFILE`, then the default one. Unless
+/// fab is `interactive`, an expired API token is an error instead of a prompt
+/// for a new one.
+pub fn init(interactive: bool, profile: Option<&str>) -> Result<FabConfig, Error> {
+    let requested_profile = profile
+        .map(str::to_string)
+        .or_else(|| env::var("FAB_PROFILE").ok());
+
+    let mut config = match read_config()? {
+        Some(config) => config,
+        None if !interactive => return Err(SetupRequired.into()),
+        None => {
+            let name = requested_profile
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+            let mut config = Profiles::default();
+            config.add(&name, set_up()?);
+            write_config(&config)?;
+            config
+        }
+    };
+
+    let name = requested_profile
+        .or_else(|| config.default_profile.clone())
+        .ok_or_else(|| {
+            InvalidArgument(
+                "There is no default profile. Pick one with `fab profile use <name>`".to_string(),
+            )
+        })?;
+    let profile = config.profiles.remove(&name).ok_or_else(|| {
+        NotFound(format!(
+            "There is no profile named {}. Add it with `fab profile add {}`",
+            name, name
+        ))
+    })?;
+
+    let client = ConduitClient::new(&profile.hosted_instance, &profile.api_token);
+    let client = if interactive {
+        let current_profile = profile.clone();
+        client.on_invalid_auth(move || renew_token(&name, &current_profile))
+    } else {
+        client
+    };
+
+    Ok(FabConfig::new(client, profile.phid))
+}
+
+impl Profiles {
+    /// Adds or replaces a profile. The first profile becomes the default one.
+    pub fn add(&mut self, name: &str, profile: Profile) {
+        self.profiles.insert(name.to_string(), profile);
+        if self.default_profile.is_none() {
+            self.default_profile = Some(name.to_string());
+        }
+    }
+}
+
+fn set_up() -> Result<Profile, Error> {
+    println!(
+        " _    _      _                            _         ______    _
 | |  | |    | |                          | |        |  ___|  | |
 | |  | | ___| | ___ ___  _ __ ___   ___  | |_ ___   | |_ __ _| |__
 | |/\\| |/ _ \\ |/ __/ _ \\| '_ ` _ \\ / _ \\ | __/ _ \\  |  _/ _` | '_ \\
 \\  /\\  /  __/ | (_| (_) | | | | | |  __/ | || (_) | | || (_| | |_) |
  \\/  \\/ \\___|_|\\___\\___/|_| |_| |_|\\___|  \\__\\___/  \\_| \\__,_|_.__/"
-            );
+    );
 
-            println!("Let's get you started!");
-            println!("Enter the URL where your Phabricator instance is hosted. Example: https://phab.mycompany.com/");
+    println!("Let's get you started!");
 
-            let hosted_instance = prompt_hosted_instance()?;
+    if let Some(profile) = import_arcrc()? {
+        return Ok(profile);
+    }
 
-            let token = prompt_token(&hosted_instance)?;
+    prompt_profile()
+}
 
-            // Get user's details
-            let phid = get_phid(&hosted_instance, &token)?;
+/// Offers to use the credentials Arcanist stored in `~/.arcrc`. Gives `None` if
+/// there are none, they're declined, or Conduit rejects them.
+fn import_arcrc() -> Result<Option<Profile>, Error> {
+    let path_buf = dirs::home_dir().unwrap();
+    let arcrc_file = format!("{}/.arcrc", path_buf.to_str().unwrap());
+
+    let arcrc = match read_to_string(&arcrc_file)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Arcrc>(&contents).ok())
+    {
+        Some(arcrc) => arcrc,
+        None => return Ok(None),
+    };
+
+    let credentials = arcrc_credentials(&arcrc);
+    if credentials.is_empty() {
+        return Ok(None);
+    }
+
+    let use_arcrc = Confirmation::with_theme(&ColorfulTheme::default())
+        .with_text("Found Arcanist credentials in ~/.arcrc. Do you want to use them?")
+        .default(true)
+        .interact()?;
+    if !use_arcrc {
+        return Ok(None);
+    }
 
-            let config = FabConfig {
+    // The default instance comes first, so there is only a choice without one.
+    let default = arcrc.config.default.as_deref().map(hosted_instance_of);
+    let (hosted_instance, api_token) = if credentials.len() == 1
+        || default.as_ref() == Some(&credentials[0].0)
+    {
+        credentials[0].clone()
+    } else {
+        println!("Which Phabricator instance do you want to use?");
+        let instances: Vec<&String> = credentials.iter().map(|(instance, _)| instance).collect();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .items(&instances)
+            .interact()?;
+        credentials[selection].clone()
+    };
+
+    match get_phid(&hosted_instance, &api_token) {
+        Ok(phid) => {
+            println!("Using {} from ~/.arcrc", hosted_instance);
+            Ok(Some(Profile {
                 hosted_instance,
-                api_token: token,
+                api_token,
                 phid,
-            };
-
-            write_config(&config)?;
-
-            Result::Ok(config)
+            }))
+        }
+        Err(error) => {
+            println!("The credentials in ~/.arcrc didn't work: {}", error);
+            Ok(None)
         }
     }
 }
 
-/// Function that will execute the network request provided by RequestBuilder and
-/// prompt for an API token if session is invalidated.
-pub async fn send<T: serde::de::DeserializeOwned>(
-    config: &FabConfig,
-    request: RequestBuilder,
-) -> Result<T, Error> {
-    let request = request.try_clone().unwrap();
-    let response = request.send().await?.json::<NetworkResponse<T>>().await?;
-
-    if response.result.is_some() {
-        return Result::Ok(response.result.unwrap());
-    } else if response.error_code.is_some() {
-        let error_code: String = response.error_code.unwrap();
-
-        if error_code.eq("ERR-INVALID-AUTH") || error_code.eq("ERR-INVALID-SESSION") {
-            println!("Your API Token has expired.");
-            let current_config = read_config()?;
-            let token = prompt_token(&config.hosted_instance);
-            match token {
-                Ok(token) => {
-                    let new_config = FabConfig {
-                        api_token: token,
-                        hosted_instance: current_config.hosted_instance,
-                        phid: current_config.phid,
-                    };
-
-                    write_config(&new_config)?;
-                }
-                Err(_err) => {}
-            }
-        }
+/// The instances and API tokens in `~/.arcrc`, with its default instance first.
+/// Hosts are keyed by their Conduit URI, e.g. `https://phab.mycompany.com/api/`.
+fn arcrc_credentials(arcrc: &Arcrc) -> Vec<(String, String)> {
+    let default = arcrc.config.default.as_deref().map(hosted_instance_of);
+
+    let mut credentials: Vec<(String, String)> = arcrc
+        .hosts
+        .iter()
+        .filter_map(|(uri, host)| Some((hosted_instance_of(uri), host.token.clone()?)))
+        .collect();
+    credentials.sort_by_key(|(hosted_instance, _)| Some(hosted_instance) != default.as_ref());
+
+    credentials
+}
+
+/// The hosted instance of a Conduit URI or instance URL from `~/.arcrc`.
+fn hosted_instance_of(uri: &str) -> String {
+    let mut hosted_instance = uri.trim_end_matches('/').to_string();
+    if hosted_instance.ends_with("/api") {
+        hosted_instance.truncate(hosted_instance.len() - "api".len());
+    } else {
+        hosted_instance.push('/');
     }
-    Err(anyhow!("Token regenerated. Please try the command again",))
+    hosted_instance
+}
+
+/// Asks for the instance and an API token, and looks up the user they belong to.
+pub fn prompt_profile() -> Result<Profile, Error> {
+    println!("Enter the URL where your Phabricator instance is hosted. Example: https://phab.mycompany.com/");
+
+    let hosted_instance = prompt_hosted_instance()?;
+
+    let token = prompt_token(&hosted_instance)?;
+
+    // Get user's details
+    let phid = get_phid(&hosted_instance, &token)?;
+
+    Ok(Profile {
+        hosted_instance,
+        api_token: token,
+        phid,
+    })
+}
+
+/// Prompts for a new API token after Conduit rejected the current one, for
+/// example because it expired, and writes it to the profile.
+fn renew_token(name: &str, profile: &Profile) -> Result<String, Error> {
+    println!("Your API Token has expired.");
+    let token = prompt_token(&profile.hosted_instance)?;
+
+    let mut config = read_config()?.unwrap_or_default();
+    config.add(
+        name,
+        Profile {
+            api_token: token.clone(),
+            ..profile.clone()
+        },
+    );
+    write_config(&config)?;
+
+    Ok(token)
 }
 
 /// Prompts for a token and writes the token to the configuration file.
@@ -117,7 +311,7 @@ fn prompt_hosted_instance() -> Result<String, Error> {
     Ok(hosted_instance)
 }
 
-fn write_config(config: &FabConfig) -> Result<(), Error> {
+pub fn write_config(config: &Profiles) -> Result<(), Error> {
     let path_buf = dirs::home_dir().unwrap();
 
     let home_dir = path_buf.to_str().unwrap();
@@ -134,36 +328,113 @@ fn write_config(config: &FabConfig) -> Result<(), Error> {
     Ok(())
 }
 
-/// Tries to read the config file
-fn read_config() -> Result<FabConfig, Error> {
+/// Reads the config file, or `None` when fab hasn't been set up yet. A
+/// configuration from before profiles existed is moved to the default profile.
+pub fn read_config() -> Result<Option<Profiles>, Error> {
     let path_buf = dirs::home_dir().unwrap();
 
     let home_dir = path_buf.to_str().unwrap();
 
     let config_file = format!("{}/.fab/config.json", home_dir);
 
-    let contents = read_to_string(&config_file)?;
+    let contents = match read_to_string(&config_file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
 
-    let config: FabConfig = ::serde_json::from_str(&contents)?;
+    let (config, migrated) = parse_config(&contents)?;
+    if migrated {
+        write_config(&config)?;
+    }
 
-    Ok(config)
+    Ok(Some(config))
+}
+
+/// Parses the config file, and whether it had to be migrated to profiles.
+fn parse_config(contents: &str) -> Result<(Profiles, bool), Error> {
+    match serde_json::from_str::<StoredConfig>(contents)? {
+        StoredConfig::Profiles(config) => Ok((config, false)),
+        StoredConfig::Single(profile) => {
+            let mut config = Profiles::default();
+            config.add(DEFAULT_PROFILE, profile);
+            Ok((config, true))
+        }
+    }
 }
 
 fn get_phid(hosted_instance: &str, api_token: &str) -> Result<String, Error> {
-    let url = format!("{}{}", hosted_instance, WHO_AM_I);
-    let json_body = json!({ "api.token": api_token });
+    let client = ConduitClient::new(hosted_instance, api_token);
 
-    let response = reqwest::blocking::Client::new()
-        .post(&url)
-        .form(&json_body)
-        .send()?
-        .json::<WhoAmIResponse>()?;
+    let user =
+        Runtime::new()?.block_on(client.call::<UserResponse>("user.whoami", &Params::new()))?;
 
-    Ok(response.result.phid)
+    Ok(user.phid)
 }
 
-#[derive(Deserialize, Debug)]
-struct NetworkResponse<T> {
-    pub error_code: Option<String>,
-    pub result: Option<T>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(hosted_instance: &str) -> Profile {
+        Profile {
+            hosted_instance: hosted_instance.to_string(),
+            api_token: "api-token".to_string(),
+            phid: "PHID-USER-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_single_config() {
+        let (config, migrated) = parse_config(
+            r#"{"hosted_instance":"https://phab.work.com/","api_token":"api-token","phid":"PHID-USER-1"}"#,
+        )
+        .unwrap();
+
+        assert!(migrated);
+        assert_eq!(config.default_profile, Some(DEFAULT_PROFILE.to_string()));
+        assert_eq!(
+            config.profiles[DEFAULT_PROFILE],
+            profile("https://phab.work.com/")
+        );
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        let mut config = Profiles::default();
+        config.add("work", profile("https://phab.work.com/"));
+        config.add("oss", profile("https://phab.oss.org/"));
+
+        let (parsed, migrated) = parse_config(&serde_json::to_string(&config).unwrap()).unwrap();
+
+        assert!(!migrated);
+        assert_eq!(parsed.default_profile, Some("work".to_string()));
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_arcrc_credentials() {
+        let arcrc: Arcrc = serde_json::from_str(
+            r#"{
+                "hosts": {
+                    "https://phab.oss.org/api/": {"token": "oss-token"},
+                    "https://phab.work.com/api/": {"token": "work-token"},
+                    "https://phab.old.com/api/": {"user": "alice", "cert": "certificate"}
+                },
+                "config": {"default": "https://phab.work.com"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            arcrc_credentials(&arcrc),
+            vec![
+                (
+                    "https://phab.work.com/".to_string(),
+                    "work-token".to_string()
+                ),
+                ("https://phab.oss.org/".to_string(), "oss-token".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,279 @@
+use crate::errors::InvalidArgument;
+use crate::output::{self, Format, Record};
+use crate::projects::{get_column, get_columns, get_project, Column};
+use crate::structs::FabConfig;
+use crate::tasks::{edit_task, get_task_record, get_tasks, Maniphest, TaskFilter, TaskRecord};
+use crate::utils::parse_object_id;
+use anyhow::Error;
+use clap::ArgMatches;
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+use fab::conduit::{Limit, Transaction};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+/// Shows the workboard of a project, or moves a task on it with
+/// `fab board <project> move T123 <column>`.
+pub fn process_board_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let project_name = matches.value_of("project").unwrap();
+
+    if matches.value_of("action") == Some("move") {
+        let (task, column) = match (matches.value_of("task"), matches.value_of("column")) {
+            (Some(task), Some(column)) => (task, column),
+            _ => {
+                return Err(InvalidArgument(format!(
+                    "Moving a task needs a task and a column. Example: fab board {} move T123 \"In Review\"",
+                    project_name
+                ))
+                .into())
+            }
+        };
+        return process_move_task(project_name, task, column, matches, config);
+    }
+
+    let format = Format::from_matches(matches)?;
+    let mut runtime = Runtime::new()?;
+
+    let project = runtime.block_on(get_project(project_name, config))?;
+    let columns: Vec<_> = runtime
+        .block_on(get_columns(&project, config))?
+        .into_iter()
+        .filter(|column| !column.fields.is_hidden)
+        .collect();
+
+    let filter = TaskFilter {
+        project_phid: Some(project.phid.clone()),
+        ..TaskFilter::default()
+    };
+    let tasks = runtime.block_on(get_tasks(
+        Limit::All,
+        &[],
+        "priority",
+        Some("open"),
+        &filter,
+        config,
+    ))?;
+
+    let cards = group_cards(&project.phid, &columns, &tasks);
+
+    if format != Format::Table {
+        let records: Vec<CardRecord> = columns
+            .iter()
+            .zip(&cards)
+            .flat_map(|(column, cards)| {
+                cards.iter().map(move |task| {
+                    let task = TaskRecord::new(task, config);
+                    CardRecord {
+                        column: column.fields.name.clone(),
+                        id: task.id,
+                        title: task.title,
+                        priority: task.priority,
+                        url: task.url,
+                    }
+                })
+            })
+            .collect();
+        return output::print_records(format, &records);
+    }
+
+    let mut table = Table::new();
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(
+        columns
+            .iter()
+            .zip(&cards)
+            .map(|(column, cards)| {
+                Cell::new(format!("{} ({})", column.fields.name, cards.len()))
+                    .add_attribute(Attribute::Bold)
+            })
+            .collect::<Vec<Cell>>(),
+    );
+
+    for row in card_rows(&cards) {
+        table.add_row(
+            row.iter()
+                .map(|card| match card {
+                    Some(task) => task.get_card(),
+                    None => Cell::new(""),
+                })
+                .collect::<Vec<Cell>>(),
+        );
+    }
+
+    println!("{}", table);
+    Ok(())
+}
+
+/// The tasks in each of the columns. Tasks that aren't in any of the columns, for
+/// example because their column is hidden, are left out.
+fn group_cards<'a>(
+    project_phid: &str,
+    columns: &[Column],
+    tasks: &'a [Maniphest],
+) -> Vec<Vec<&'a Maniphest>> {
+    columns
+        .iter()
+        .map(|column| {
+            tasks
+                .iter()
+                .filter(|task| task.is_in_column(project_phid, &column.phid))
+                .collect()
+        })
+        .collect()
+}
+
+/// The rows of the board, with a card or a gap for every column. There are as many
+/// rows as there are cards in the fullest column.
+fn card_rows<'a>(cards: &[Vec<&'a Maniphest>]) -> Vec<Vec<Option<&'a Maniphest>>> {
+    let rows = cards.iter().map(Vec::len).max().unwrap_or(0);
+
+    (0..rows)
+        .map(|row| cards.iter().map(|cards| cards.get(row).copied()).collect())
+        .collect()
+}
+
+fn process_move_task(
+    project_name: &str,
+    task: &str,
+    column_name: &str,
+    matches: &ArgMatches,
+    config: &FabConfig,
+) -> Result<(), Error> {
+    let id = parse_object_id('T', task)?;
+    let format = Format::from_matches(matches)?;
+
+    let mut runtime = Runtime::new()?;
+
+    let project = runtime.block_on(get_project(project_name, config))?;
+    let column = runtime.block_on(get_column(&project, column_name, config))?;
+
+    runtime.block_on(edit_task(
+        config,
+        id,
+        &[Transaction::new(
+            "column",
+            Value::Array(vec![Value::from(column.phid)]),
+        )],
+    ))?;
+
+    if format != Format::Table {
+        return output::print_record(format, &runtime.block_on(get_task_record(config, id))?);
+    }
+
+    println!(
+        "Moved T{} to {} on {}",
+        id, column.fields.name, project.fields.name
+    );
+    Ok(())
+}
+
+/// A task on a workboard as printed with `--format`. See `output::Record`.
+#[derive(Debug, Serialize)]
+struct CardRecord {
+    column: String,
+    id: i32,
+    title: String,
+    priority: String,
+    url: String,
+}
+
+impl Record for CardRecord {
+    const FIELDS: &'static [&'static str] = &["column", "id", "title", "priority", "url"];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::ColumnFields;
+
+    fn column(id: i32, name: &str) -> Column {
+        Column {
+            id,
+            phid: format!("PHID-PCOL-{}", id),
+            fields: ColumnFields {
+                name: name.to_string(),
+                sequence: id,
+                is_hidden: false,
+            },
+        }
+    }
+
+    fn task(id: i32, boards: Value) -> Maniphest {
+        serde_json::from_value(json!({
+            "id": id,
+            "fields": {
+                "name": format!("Task {}", id),
+                "status": {"name": "Open", "value": "open"},
+                "priority": {"name": "Normal", "value": 50},
+            },
+            "attachments": {"columns": {"boards": boards}},
+        }))
+        .unwrap()
+    }
+
+    fn in_column(column_id: i32) -> Value {
+        json!({
+            "PHID-PROJ-1": {
+                "columns": [{"id": column_id, "phid": format!("PHID-PCOL-{}", column_id)}]
+            }
+        })
+    }
+
+    fn ids(cards: &[&Maniphest]) -> Vec<i32> {
+        cards.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn test_group_cards() {
+        let columns = [column(1, "Backlog"), column(2, "Doing"), column(3, "Done")];
+        let tasks = [
+            task(10, in_column(1)),
+            task(11, in_column(2)),
+            task(12, in_column(1)),
+            // Not on the board, or in a hidden column.
+            task(13, json!([])),
+            task(14, in_column(4)),
+        ];
+
+        let cards = group_cards("PHID-PROJ-1", &columns, &tasks);
+
+        assert_eq!(3, cards.len());
+        assert_eq!(vec![10, 12], ids(&cards[0]));
+        assert_eq!(vec![11], ids(&cards[1]));
+        assert!(cards[2].is_empty());
+    }
+
+    #[test]
+    fn test_card_rows() {
+        let columns = [column(1, "Backlog"), column(2, "Doing"), column(3, "Done")];
+        let tasks = [
+            task(10, in_column(1)),
+            task(11, in_column(2)),
+            task(12, in_column(1)),
+        ];
+        let cards = group_cards("PHID-PROJ-1", &columns, &tasks);
+
+        let rows: Vec<Vec<Option<i32>>> = card_rows(&cards)
+            .iter()
+            .map(|row| row.iter().map(|card| card.map(|task| task.id)).collect())
+            .collect();
+
+        assert_eq!(
+            vec![vec![Some(10), Some(11), None], vec![Some(12), None, None]],
+            rows
+        );
+    }
+
+    #[test]
+    fn test_empty_board() {
+        let tasks = [task(10, json!([]))];
+
+        assert!(card_rows(&group_cards("PHID-PROJ-1", &[], &tasks)).is_empty());
+
+        let columns = [column(1, "Backlog")];
+        let cards = group_cards("PHID-PROJ-1", &columns, &tasks);
+        assert_eq!(1, cards.len());
+        assert!(card_rows(&cards).is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::diffs::get_revision;
+use crate::output::{self, Format, Record};
+use crate::structs::{FabConfig, Revision};
+use crate::utils::parse_object_id;
+use crate::NO_BORDER_PRESET;
+use anyhow::{anyhow, Error};
+use clap::ArgMatches;
+use comfy_table::{Attribute, Cell, CellAlignment, Color, ContentArrangement, Table};
+use fab::conduit::{Limit, Search};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::runtime::Runtime;
+
+const BUILDABLE_SEARCH: &str = "harbormaster.buildable.search";
+const BUILD_SEARCH: &str = "harbormaster.build.search";
+const TARGET_SEARCH: &str = "harbormaster.target.search";
+const LOG_SEARCH: &str = "harbormaster.log.search";
+
+/// Get the buildables of the given diffs.
+pub async fn get_buildables(
+    config: &FabConfig,
+    diff_phids: &[String],
+) -> Result<Vec<Buildable>, Error> {
+    if diff_phids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let search = Search::new(BUILDABLE_SEARCH).constraints("objectPHIDs", diff_phids);
+
+    config.client().search(&search, Limit::All).await
+}
+
+/// Get the build status of the latest diff of each revision, keyed by the diff PHID.
+/// Build statuses are an extra, so when Harbormaster can't be searched, for example
+/// because it isn't installed or the token can't use it, there are none.
+pub async fn get_build_statuses(
+    config: &FabConfig,
+    revisions: &[&Revision],
+) -> HashMap<String, BuildStatus> {
+    let diff_phids: Vec<String> = revisions
+        .iter()
+        .map(|revision| revision.fields.diff_phid.clone())
+        .collect();
+
+    get_buildables(config, &diff_phids)
+        .await
+        .map(|buildables| {
+            buildables
+                .into_iter()
+                .map(|buildable| (buildable.fields.object_phid, buildable.fields.status))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn get_builds(config: &FabConfig, buildable_phid: &str) -> Result<Vec<Build>, Error> {
+    let search = Search::new(BUILD_SEARCH).constraints("buildables", &[buildable_phid]);
+
+    config.client().search(&search, Limit::All).await
+}
+
+/// Get the targets of all the given builds at once.
+async fn get_targets(config: &FabConfig, build_phids: &[&str]) -> Result<Vec<BuildTarget>, Error> {
+    if build_phids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let search = Search::new(TARGET_SEARCH).constraints("buildPHIDs", build_phids);
+
+    config.client().search(&search, Limit::All).await
+}
+
+/// Get the log URLs of all the given build targets at once, keyed by the target PHID.
+async fn get_log_urls(
+    config: &FabConfig,
+    target_phids: &[&str],
+) -> Result<HashMap<String, Vec<String>>, Error> {
+    if target_phids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let search = Search::new(LOG_SEARCH).constraints("buildTargetPHIDs", target_phids);
+    let logs: Vec<BuildLog> = config.client().search(&search, Limit::All).await?;
+
+    let mut log_urls: HashMap<String, Vec<String>> = HashMap::new();
+    for log in logs {
+        log_urls
+            .entry(log.fields.target_phid.clone())
+            .or_default()
+            .push(log.url(config));
+    }
+    Ok(log_urls)
+}
+
+pub fn process_builds_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = parse_object_id('D', matches.value_of("id").unwrap())?;
+    let format = Format::from_matches(matches)?;
+
+    let mut runtime = Runtime::new()?;
+
+    let revision = runtime.block_on(get_revision(config, id))?;
+
+    let buildable = runtime
+        .block_on(get_buildables(config, &[revision.fields.diff_phid.clone()]))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("The latest diff of D{} doesn't have any builds", id))?;
+
+    let builds = runtime.block_on(get_builds(config, &buildable.phid))?;
+
+    let build_phids: Vec<&str> = builds.iter().map(|build| build.phid.as_str()).collect();
+    let targets = runtime.block_on(get_targets(config, &build_phids))?;
+
+    let target_phids: Vec<&str> = targets.iter().map(|target| target.phid.as_str()).collect();
+    let log_urls = runtime.block_on(get_log_urls(config, &target_phids))?;
+
+    let targets_of = |build: &Build| -> Vec<&BuildTarget> {
+        targets
+            .iter()
+            .filter(|target| target.fields.build_phid == build.phid)
+            .collect()
+    };
+    let logs_of = |target: &BuildTarget| -> Vec<String> {
+        log_urls.get(&target.phid).cloned().unwrap_or_default()
+    };
+
+    if format != Format::Table {
+        let mut records = vec![];
+        for build in &builds {
+            for target in targets_of(build) {
+                records.push(BuildTargetRecord {
+                    build_id: build.id,
+                    build: build.fields.name.clone(),
+                    build_status: build.fields.status.value.clone(),
+                    target: target.fields.name.clone(),
+                    status: target.fields.status.value.clone(),
+                    logs: logs_of(target),
+                });
+            }
+        }
+        return output::print_records(format, &records);
+    }
+
+    let mut table = Table::new();
+
+    table
+        .load_preset(NO_BORDER_PRESET)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for build in &builds {
+        table.add_row(vec![
+            build.fields.status.cell(),
+            Cell::new(&build.fields.name).add_attribute(Attribute::Bold),
+            Cell::new(format!(
+                "{}harbormaster/build/{}/",
+                config.hosted_instance, build.id
+            )),
+        ]);
+
+        for target in targets_of(build) {
+            table.add_row(vec![
+                target.fields.status.cell(),
+                Cell::new(format!("  {}", target.fields.name)),
+                Cell::new(logs_of(target).join("\n")),
+            ]);
+        }
+    }
+
+    println!("{}", table);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Buildable {
+    pub phid: String,
+    pub fields: BuildableFields,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildableFields {
+    #[serde(rename = "objectPHID")]
+    pub object_phid: String,
+    #[serde(rename = "buildableStatus")]
+    pub status: BuildStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct Build {
+    id: i32,
+    phid: String,
+    fields: BuildFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildFields {
+    name: String,
+    #[serde(rename = "buildStatus")]
+    status: BuildStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildTarget {
+    phid: String,
+    fields: BuildTargetFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildTargetFields {
+    name: String,
+    #[serde(rename = "buildPHID")]
+    build_phid: String,
+    status: BuildStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildLog {
+    id: i32,
+    fields: BuildLogFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildLogFields {
+    #[serde(rename = "buildTargetPHID")]
+    target_phid: String,
+}
+
+impl BuildLog {
+    fn url(&self, config: &FabConfig) -> String {
+        format!(
+            "{}harbormaster/log/view/{}/",
+            config.hosted_instance, self.id
+        )
+    }
+}
+
+/// A build target of a revision as printed with `--format`. See `output::Record`.
+#[derive(Debug, Serialize)]
+struct BuildTargetRecord {
+    build_id: i32,
+    build: String,
+    build_status: String,
+    target: String,
+    status: String,
+    logs: Vec<String>,
+}
+
+impl Record for BuildTargetRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "build_id",
+        "build",
+        "build_status",
+        "target",
+        "status",
+        "logs",
+    ];
+}
+
+/// Status of a buildable, build or build target, e.g. `passed`, `failed` or `building`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BuildStatus {
+    pub value: String,
+}
+
+impl BuildStatus {
+    pub fn get_background(&self) -> Color {
+        match self.value.as_str() {
+            "passed" => Color::Green,
+            "failed" | "aborted" | "error" => Color::Red,
+            _ => Color::Yellow,
+        }
+    }
+
+    pub fn get_foreground(&self) -> Color {
+        match self.value.as_str() {
+            "failed" | "aborted" | "error" => Color::White,
+            _ => Color::Black,
+        }
+    }
+
+    pub fn cell(&self) -> Cell {
+        Cell::new(&self.value)
+            .bg(self.get_background())
+            .fg(self.get_foreground())
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_status_colors() {
+        let status = BuildStatus {
+            value: String::from("passed"),
+        };
+        assert_eq!(Color::Green, status.get_background());
+        assert_eq!(Color::Black, status.get_foreground());
+
+        let status = BuildStatus {
+            value: String::from("failed"),
+        };
+        assert_eq!(Color::Red, status.get_background());
+        assert_eq!(Color::White, status.get_foreground());
+
+        let status = BuildStatus {
+            value: String::from("building"),
+        };
+        assert_eq!(Color::Yellow, status.get_background());
+        assert_eq!(Color::Black, status.get_foreground());
+    }
+}
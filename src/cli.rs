@@ -1,8 +1,26 @@
+use crate::output::FORMATS;
 use crate::preferences::Preferences;
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg};
 
 pub const VERSION: &str = "0.4.2";
 
+/// Task priorities that can be passed on the command line.
+pub const TASK_PRIORITIES: [&str; 6] = [
+    "unbreak-now",
+    "needs-triage",
+    "high",
+    "normal",
+    "low",
+    "wishlist",
+];
+
+/// Task statuses that can be passed on the command line.
+pub const TASK_STATUSES: [&str; 5] = ["open", "resolved", "wontfix", "invalid", "duplicate"];
+
+/// Task statuses that can be set with `fab task edit`. Maniphest only makes a task
+/// a duplicate by merging it into another one.
+pub const EDITABLE_TASK_STATUSES: [&str; 4] = ["open", "resolved", "wontfix", "invalid"];
+
 /// Builds the App with commands and defaults.
 pub fn build_cli(preferences: &Preferences) -> App {
     let default_task_priority: &Vec<&str> = &preferences
@@ -17,6 +35,27 @@ pub fn build_cli(preferences: &Preferences) -> App {
     App::new("Fab")
         .author("Shaishav <shaishavgandhi05@gmail.com>")
         .version(VERSION)
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&FORMATS)
+                .help("Print results as a table or in a machine-readable format"),
+        )
+        .arg(
+            Arg::with_name("non-interactive")
+                .long("non-interactive")
+                .global(true)
+                .help("Fail instead of prompting for an API token, e.g. in scripts and CI"),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .global(true)
+                .takes_value(true)
+                .help("The profile to use instead of the default one. Can also be set with FAB_PROFILE"),
+        )
         .subcommand(
             App::new("diffs")
                 .version(VERSION)
@@ -37,6 +76,207 @@ pub fn build_cli(preferences: &Preferences) -> App {
                         .help(
                             "Find diffs with given author name. This is the Phabricator username",
                         ),
+                )
+                .arg(
+                    Arg::with_name("query-key")
+                        .long("query-key")
+                        .takes_value(true)
+                        .conflicts_with_all(&["needs-review", "author"])
+                        .help("Show the revisions of a saved query of the web UI by its query key"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short('l')
+                        .long("limit")
+                        .help("limit results by a value")
+                        .default_value(&default_limit),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Fetch all results, ignoring --limit"),
+                )
+                .arg(columns_arg())
+                .arg(template_arg())
+                .arg(
+                    Arg::with_name("quiet")
+                        .short('q')
+                        .long("quiet")
+                        .help("Print nothing and exit with 1 if there are any revisions, e.g. in git hooks"),
+                )
+        )
+        .subcommand(
+            App::new("diff")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Show the details of a single differential revision")
+                .arg(
+                    Arg::with_name("id").index(1).help(
+                        "The revision to show. Example: D1234. Defaults to the revision of the current git branch",
+                    ),
+                )
+                .arg(
+                    Arg::with_name("open")
+                        .short('o')
+                        .long("open")
+                        .help("Open the revision in the browser"),
+                )
+                .arg(
+                    Arg::with_name("comments")
+                        .short('c')
+                        .long("comments")
+                        .help("Show the discussion and inline comments of the revision"),
+                ),
+        )
+        .subcommand(
+            App::new("review")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Accept, reject or otherwise act on a differential revision")
+                .arg(
+                    Arg::with_name("id")
+                        .index(1)
+                        .required(true)
+                        .help("The revision to act on. Example: D1234"),
+                )
+                .arg(
+                    Arg::with_name("action")
+                        .index(2)
+                        .required(true)
+                        .possible_values(&[
+                            "accept",
+                            "reject",
+                            "request-changes",
+                            "resign",
+                            "plan-changes",
+                            "abandon",
+                            "reclaim",
+                            "reopen",
+                            "commandeer",
+                        ])
+                        .help("The action to take on the revision"),
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .short('m')
+                        .long("message")
+                        .takes_value(true)
+                        .help("Comment to leave along with the action"),
+                ),
+        )
+        .subcommand(
+            App::new("comment")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Post a comment on a revision or a task")
+                .arg(
+                    Arg::with_name("object")
+                        .index(1)
+                        .required(true)
+                        .help("The revision or task to comment on. Example: D1234 or T1234"),
+                )
+                .arg(Arg::with_name("message").index(2).help(
+                    "The comment to post. Use `-` to read it from stdin. Opens $EDITOR if missing",
+                )),
+        )
+        .subcommand(
+            App::new("reviewers")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Add or remove reviewers of a differential revision")
+                .arg(
+                    Arg::with_name("id")
+                        .index(1)
+                        .required(true)
+                        .help("The revision to change the reviewers of. Example: D1234"),
+                )
+                .arg(
+                    Arg::with_name("action")
+                        .index(2)
+                        .required(true)
+                        .possible_values(&["add", "remove"])
+                        .help("Whether to add or remove the reviewers"),
+                )
+                .arg(
+                    Arg::with_name("users")
+                        .index(3)
+                        .required(true)
+                        .multiple(true)
+                        .help("Phabricator usernames of the reviewers"),
+                )
+                .arg(
+                    Arg::with_name("blocking")
+                        .short('b')
+                        .long("blocking")
+                        .help("Add the reviewers as blocking reviewers. Only works with `add`"),
+                ),
+        )
+        .subcommand(
+            App::new("subscribe")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Subscribe to a revision or a task")
+                .arg(
+                    Arg::with_name("object")
+                        .index(1)
+                        .required(true)
+                        .help("The revision or task to subscribe to. Example: D1234 or T1234"),
+                )
+                .arg(
+                    Arg::with_name("users")
+                        .index(2)
+                        .multiple(true)
+                        .help("Phabricator usernames to subscribe. Defaults to yourself"),
+                ),
+        )
+        .subcommand(
+            App::new("submit")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Create or update a revision from the current git branch")
+                .arg(
+                    Arg::with_name("base")
+                        .short('b')
+                        .long("base")
+                        .takes_value(true)
+                        .help("The git ref to diff the current branch against. Defaults to the default branch of origin"),
+                ),
+        )
+        .subcommand(
+            App::new("patch")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Apply the patch of a revision to a new git branch")
+                .arg(
+                    Arg::with_name("id")
+                        .index(1)
+                        .required(true)
+                        .help("The revision to patch. Example: D1234"),
+                )
+                .arg(
+                    Arg::with_name("diff")
+                        .short('d')
+                        .long("diff")
+                        .takes_value(true)
+                        .help("The id of the diff to apply. Defaults to the latest diff"),
+                )
+                .arg(
+                    Arg::with_name("print")
+                        .short('p')
+                        .long("print")
+                        .help("Print the patch to stdout instead of applying it"),
+                ),
+        )
+        .subcommand(
+            App::new("builds")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .about("Show the Harbormaster builds of a revision's latest diff")
+                .arg(
+                    Arg::with_name("id")
+                        .index(1)
+                        .required(true)
+                        .help("The revision whose builds to show. Example: D1234"),
                 ),
         )
         .subcommand(
@@ -48,14 +288,7 @@ pub fn build_cli(preferences: &Preferences) -> App {
                     Arg::with_name("priority")
                         .short('p')
                         .long("priority")
-                        .possible_values(&[
-                            "unbreak-now",
-                            "needs-triage",
-                            "high",
-                            "normal",
-                            "low",
-                            "wishlist",
-                        ])
+                        .possible_values(&TASK_PRIORITIES)
                         .help("Specify the priority of the task")
                         .default_values(default_task_priority)
                         .multiple(true),
@@ -68,6 +301,19 @@ pub fn build_cli(preferences: &Preferences) -> App {
                         // .default_value(preferences.get_default_limit().clone().as_str())
                         .default_value(&default_limit),
                 )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Fetch all results, ignoring --limit"),
+                )
+                .arg(columns_arg())
+                .arg(template_arg())
+                .arg(
+                    Arg::with_name("quiet")
+                        .short('q')
+                        .long("quiet")
+                        .help("Print nothing and exit with 1 if there are any tasks, e.g. in git hooks"),
+                )
                 .arg(
                     Arg::with_name("sort")
                         .short('s')
@@ -81,8 +327,316 @@ pub fn build_cli(preferences: &Preferences) -> App {
                         .short('S')
                         .long("status")
                         .help("Filter tasks by status")
-                        .possible_values(&["open", "resolved", "wontfix", "invalid", "duplicate"])
+                        .possible_values(&TASK_STATUSES)
                         .default_value("open"),
+                )
+                .arg(
+                    Arg::with_name("project")
+                        .short('P')
+                        .long("project")
+                        .takes_value(true)
+                        .help("Show tasks of a project instead of the tasks assigned to you"),
+                )
+                .arg(
+                    Arg::with_name("column")
+                        .short('c')
+                        .long("column")
+                        .takes_value(true)
+                        .requires("project")
+                        .help("Only show tasks in this column of the project's workboard"),
+                )
+                .arg(
+                    Arg::with_name("query-key")
+                        .long("query-key")
+                        .takes_value(true)
+                        .help("Show the tasks of a saved query of the web UI by its query key"),
+                ),
+        )
+        .subcommand(
+            App::new("task")
+                .about("Commands related to a single maniphest task")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::with_name("id")
+                        .index(1)
+                        .help("The task to show. Example: T1234"),
+                )
+                .subcommand(
+                    App::new("create")
+                        .about("Create a new task")
+                        .arg(
+                            Arg::with_name("title")
+                                .short('t')
+                                .long("title")
+                                .required(true)
+                                .takes_value(true)
+                                .help("Title of the task"),
+                        )
+                        .arg(
+                            Arg::with_name("description")
+                                .short('d')
+                                .long("description")
+                                .takes_value(true)
+                                .help("Description of the task. Use `-` to read it from stdin"),
+                        )
+                        .arg(
+                            Arg::with_name("edit")
+                                .short('e')
+                                .long("edit")
+                                .conflicts_with("description")
+                                .help("Write the description in $EDITOR"),
+                        )
+                        .arg(
+                            Arg::with_name("priority")
+                                .short('p')
+                                .long("priority")
+                                .takes_value(true)
+                                .possible_values(&TASK_PRIORITIES)
+                                .help("Priority of the task"),
+                        )
+                        .arg(
+                            Arg::with_name("assign")
+                                .short('a')
+                                .long("assign")
+                                .takes_value(true)
+                                .help("Phabricator username of the assignee"),
+                        )
+                        .arg(
+                            Arg::with_name("project")
+                                .short('P')
+                                .long("project")
+                                .takes_value(true)
+                                .multiple(true)
+                                .help("Names of the projects to tag the task with"),
+                        )
+                        .arg(
+                            Arg::with_name("subscriber")
+                                .short('s')
+                                .long("subscriber")
+                                .takes_value(true)
+                                .multiple(true)
+                                .help("Phabricator usernames of the subscribers"),
+                        )
+                        .arg(
+                            Arg::with_name("parent")
+                                .long("parent")
+                                .takes_value(true)
+                                .help("Create the task as a subtask of this task. Example: T1234"),
+                        ),
+                )
+                .subcommand(
+                    App::new("edit")
+                        .about("Edit the status, priority, assignee, title or projects of a task")
+                        .arg(
+                            Arg::with_name("id")
+                                .index(1)
+                                .required(true)
+                                .help("The task to edit. Example: T1234"),
+                        )
+                        .arg(
+                            Arg::with_name("status")
+                                .short('S')
+                                .long("status")
+                                .takes_value(true)
+                                .possible_values(&EDITABLE_TASK_STATUSES)
+                                .help("New status of the task"),
+                        )
+                        .arg(
+                            Arg::with_name("priority")
+                                .short('p')
+                                .long("priority")
+                                .takes_value(true)
+                                .possible_values(&TASK_PRIORITIES)
+                                .help("New priority of the task"),
+                        )
+                        .arg(
+                            Arg::with_name("assign")
+                                .short('a')
+                                .long("assign")
+                                .takes_value(true)
+                                .help("Phabricator username of the new assignee"),
+                        )
+                        .arg(
+                            Arg::with_name("claim")
+                                .short('c')
+                                .long("claim")
+                                .conflicts_with("assign")
+                                .help("Assign the task to yourself"),
+                        )
+                        .arg(
+                            Arg::with_name("title")
+                                .short('t')
+                                .long("title")
+                                .takes_value(true)
+                                .help("New title of the task"),
+                        )
+                        .arg(
+                            Arg::with_name("add-project")
+                                .long("add-project")
+                                .takes_value(true)
+                                .multiple(true)
+                                .help("Names of projects to tag the task with"),
+                        )
+                        .arg(
+                            Arg::with_name("remove-project")
+                                .long("remove-project")
+                                .takes_value(true)
+                                .multiple(true)
+                                .help("Names of projects to remove from the task"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("board")
+                .about("Show the workboard of a project or move tasks between its columns")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .arg(
+                    Arg::with_name("project")
+                        .index(1)
+                        .required(true)
+                        .help("Name of the project"),
+                )
+                .arg(
+                    Arg::with_name("action")
+                        .index(2)
+                        .possible_values(&["move"])
+                        .help("Move a task to another column"),
+                )
+                .arg(
+                    Arg::with_name("task")
+                        .index(3)
+                        .help("The task to move. Example: T1234"),
+                )
+                .arg(
+                    Arg::with_name("column")
+                        .index(4)
+                        .help("Name of the column to move the task to"),
+                ),
+        )
+        .subcommand(
+            App::new("search")
+                .about("Search tasks and revisions of anyone")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .arg(
+                    Arg::with_name("query")
+                        .index(1)
+                        .required(true)
+                        .help("The text to search for"),
+                )
+                .arg(
+                    Arg::with_name("type")
+                        .short('t')
+                        .long("type")
+                        .possible_values(&["all", "task", "diff"])
+                        .default_value("all")
+                        .help("Only search tasks or revisions"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short('l')
+                        .long("limit")
+                        .help("limit results by a value")
+                        .default_value(&default_limit),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Fetch all results, ignoring --limit"),
+                )
+                .arg(columns_arg())
+                .arg(template_arg()),
+        )
+        .subcommand(
+            App::new("query")
+                .about("Save combinations of filters to run them again with `fab q`")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    App::new("save")
+                        .about("Save a fab command under a name. Example: fab query save standup tasks --priority high")
+                        .setting(AppSettings::TrailingVarArg)
+                        .arg(
+                            Arg::with_name("name")
+                                .index(1)
+                                .required(true)
+                                .help("The name of the query"),
+                        )
+                        .arg(
+                            Arg::with_name("args")
+                                .index(2)
+                                .required(true)
+                                .multiple(true)
+                                .allow_hyphen_values(true)
+                                .help("The fab command to save along with its arguments"),
+                        ),
+                )
+                .subcommand(App::new("list").about("List the saved queries"))
+                .subcommand(
+                    App::new("remove").about("Remove a saved query").arg(
+                        Arg::with_name("name")
+                            .index(1)
+                            .required(true)
+                            .help("The name of the query"),
+                    ),
+                ),
+        )
+        .subcommand(
+            App::new("q")
+                .about("Run a saved query")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .setting(AppSettings::TrailingVarArg)
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .required(true)
+                        .help("The name of the query"),
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .index(2)
+                        .multiple(true)
+                        .allow_hyphen_values(true)
+                        .help("More arguments to add to the saved ones. Options given again replace the saved ones, e.g. --limit 5"),
+                ),
+        )
+        .subcommand(
+            App::new("profile")
+                .about("Manage profiles for the Phabricator instances you work with")
+                .version(VERSION)
+                .author("Shaishav <shaishavgandhi05@gmail.com>")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    App::new("add")
+                        .about("Add a profile for another instance or account")
+                        .arg(
+                            Arg::with_name("name")
+                                .index(1)
+                                .required(true)
+                                .help("The name of the profile, e.g. work"),
+                        ),
+                )
+                .subcommand(App::new("list").about("List the profiles"))
+                .subcommand(
+                    App::new("remove").about("Remove a profile").arg(
+                        Arg::with_name("name")
+                            .index(1)
+                            .required(true)
+                            .help("The name of the profile"),
+                    ),
+                )
+                .subcommand(
+                    App::new("use").about("Use a profile by default").arg(
+                        Arg::with_name("name")
+                            .index(1)
+                            .required(true)
+                            .help("The name of the profile"),
+                    ),
                 ),
         )
         .subcommand(
@@ -118,3 +672,20 @@ pub fn build_cli(preferences: &Preferences) -> App {
                 .author("Shaishav <shaishavgandhi05@gmail.com>"),
         )
 }
+
+/// `--columns` of the listings that can be printed with `output::Output`.
+fn columns_arg<'help>() -> Arg<'help> {
+    Arg::with_name("columns")
+        .long("columns")
+        .takes_value(true)
+        .help("Comma separated fields to show, e.g. id,priority,title,url,updated")
+}
+
+/// `--template` of the listings that can be printed with `output::Output`.
+fn template_arg<'help>() -> Arg<'help> {
+    Arg::with_name("template")
+        .long("template")
+        .takes_value(true)
+        .conflicts_with("columns")
+        .help("Print every result on a line filled in from a template, e.g. '{id}\\t{title}'")
+}
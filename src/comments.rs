@@ -0,0 +1,267 @@
+use crate::output::{self, Format, Record};
+use crate::phids::Handle;
+use crate::structs::{FabConfig, TransactionItem};
+use crate::transactions::{edit_object, print_object};
+use crate::utils::{format_timestamp, read_message};
+use crate::{phids, remarkup};
+use anyhow::Error;
+use clap::ArgMatches;
+use console::style;
+use fab::conduit::{Limit, Search, Transaction};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use tokio::runtime::Runtime;
+
+const TRANSACTION_SEARCH: &str = "transaction.search";
+
+/// Get the transactions of an object such as `D1234`, newest first.
+pub async fn get_transactions(
+    config: &FabConfig,
+    object_identifier: &str,
+) -> Result<Vec<TransactionItem>, Error> {
+    let search = Search::new(TRANSACTION_SEARCH).param("objectIdentifier", object_identifier);
+
+    config.client().search(&search, Limit::All).await
+}
+
+/// Prints the comments as the discussion view or in one of the machine-readable
+/// formats, in which every comment is a record in chronological order.
+pub fn print_comments(
+    format: Format,
+    transactions: &[TransactionItem],
+    handles: &HashMap<String, Handle>,
+) -> Result<(), Error> {
+    if format == Format::Table {
+        render_comments(transactions, handles);
+        return Ok(());
+    }
+
+    let mut records: Vec<CommentRecord> = transactions
+        .iter()
+        .filter_map(|transaction| CommentRecord::new(transaction, handles))
+        .collect();
+    records.sort_by_key(|record| record.date_created);
+    output::print_records(format, &records)
+}
+
+/// Prints the discussion of an object in chronological order followed by the
+/// inline comments grouped by file and line.
+pub fn render_comments(transactions: &[TransactionItem], handles: &HashMap<String, Handle>) {
+    let mut discussion: Vec<&TransactionItem> = transactions
+        .iter()
+        .filter(|transaction| !transaction.is_inline() && transaction.comment().is_some())
+        .collect();
+    discussion.sort_by_key(|transaction| transaction.date_created);
+
+    println!("{}", style("Discussion").bold().underlined());
+    println!();
+
+    if discussion.is_empty() {
+        println!("No comments yet");
+        println!();
+    }
+
+    for transaction in discussion {
+        render_comment(transaction, handles, "");
+    }
+
+    let inline_comments = group_inline_comments(transactions);
+
+    if inline_comments.is_empty() {
+        return;
+    }
+
+    println!("{}", style("Inline comments").bold().underlined());
+    println!();
+
+    for (path, lines) in inline_comments {
+        println!("{}", style(path).bold().cyan());
+        for (line, comments) in lines {
+            println!("  {}", style(format!("Line {}", line)).bold());
+            for transaction in comments {
+                render_comment(transaction, handles, "    ");
+            }
+        }
+    }
+}
+
+fn render_comment(transaction: &TransactionItem, handles: &HashMap<String, Handle>, indent: &str) {
+    println!(
+        "{}{} {}",
+        indent,
+        style(phids::get_name(handles, &transaction.author_phid)).bold(),
+        style(format_timestamp(transaction.date_created)).dim()
+    );
+    for line in remarkup::render(transaction.comment().unwrap_or_default()).lines() {
+        println!("{}  {}", indent, line);
+    }
+    println!();
+}
+
+/// A comment as printed with `--format`. See `output::Record`. Inline comments
+/// have a path and a line.
+#[derive(Debug, Serialize)]
+pub struct CommentRecord {
+    pub id: i32,
+    pub author: String,
+    pub author_phid: String,
+    pub comment: String,
+    pub path: Option<String>,
+    pub line: Option<i64>,
+    pub date_created: i64,
+}
+
+impl CommentRecord {
+    fn new(
+        transaction: &TransactionItem,
+        handles: &HashMap<String, Handle>,
+    ) -> Option<CommentRecord> {
+        let comment = transaction.comment()?;
+        let inline = transaction.is_inline();
+        Some(CommentRecord {
+            id: transaction.id,
+            author: phids::get_name(handles, &transaction.author_phid),
+            author_phid: transaction.author_phid.clone(),
+            comment: comment.to_string(),
+            path: transaction.path().filter(|_| inline).map(str::to_string),
+            line: transaction.line().filter(|_| inline),
+            date_created: transaction.date_created,
+        })
+    }
+}
+
+impl Record for CommentRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "author",
+        "author_phid",
+        "comment",
+        "path",
+        "line",
+        "date_created",
+    ];
+}
+
+/// Groups inline comments by their path and then by their line, with the
+/// comments on each line in chronological order.
+fn group_inline_comments(
+    transactions: &[TransactionItem],
+) -> BTreeMap<&str, BTreeMap<i64, Vec<&TransactionItem>>> {
+    let mut grouped: BTreeMap<&str, BTreeMap<i64, Vec<&TransactionItem>>> = BTreeMap::new();
+
+    for transaction in transactions {
+        if !transaction.is_inline() || transaction.comment().is_none() {
+            continue;
+        }
+
+        grouped
+            .entry(transaction.path().unwrap_or("Unknown file"))
+            .or_default()
+            .entry(transaction.line().unwrap_or(0))
+            .or_default()
+            .push(transaction);
+    }
+
+    for lines in grouped.values_mut() {
+        for comments in lines.values_mut() {
+            comments.sort_by_key(|transaction| transaction.date_created);
+        }
+    }
+
+    grouped
+}
+
+/// Posts a comment on a revision or a task, e.g. `fab comment D1234 "Looks good"`.
+pub fn process_comment_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let object = matches.value_of("object").unwrap();
+    let message = read_message(matches.value_of("message"))?;
+
+    let format = Format::from_matches(matches)?;
+
+    let transactions = [Transaction::comment(&message)];
+
+    let mut runtime = Runtime::new()?;
+    let url = runtime.block_on(edit_object(config, object, &transactions))?;
+
+    if format != Format::Table {
+        return runtime.block_on(print_object(format, config, object));
+    }
+
+    println!("Comment posted on {}", url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{CommentContent, TransactionComment};
+    use serde_json::Value;
+
+    fn transaction(
+        transaction_type: &str,
+        date_created: i64,
+        text: &str,
+        fields: Value,
+    ) -> TransactionItem {
+        TransactionItem {
+            id: 1,
+            transaction_type: Some(transaction_type.to_string()),
+            author_phid: String::from("PHID-USER-1"),
+            date_created,
+            comments: vec![TransactionComment {
+                removed: false,
+                content: CommentContent {
+                    raw: text.to_string(),
+                },
+            }],
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_group_inline_comments() {
+        let transactions = vec![
+            transaction(
+                "inline",
+                3,
+                "Reply",
+                json!({"path": "src/main.rs", "line": 10}),
+            ),
+            transaction(
+                "inline",
+                1,
+                "Typo",
+                json!({"path": "src/main.rs", "line": 10}),
+            ),
+            transaction(
+                "inline",
+                2,
+                "Rename",
+                json!({"path": "src/main.rs", "line": 4}),
+            ),
+            transaction(
+                "inline",
+                2,
+                "Test?",
+                json!({"path": "src/cli.rs", "line": 1}),
+            ),
+            transaction("comment", 5, "Looks good", json!([])),
+        ];
+
+        let grouped = group_inline_comments(&transactions);
+
+        assert_eq!(
+            vec!["src/cli.rs", "src/main.rs"],
+            grouped.keys().cloned().collect::<Vec<_>>()
+        );
+
+        let main_rs = &grouped["src/main.rs"];
+        assert_eq!(vec![4, 10], main_rs.keys().cloned().collect::<Vec<_>>());
+
+        let line_ten: Vec<&str> = main_rs[&10]
+            .iter()
+            .map(|transaction| transaction.comment().unwrap())
+            .collect();
+        assert_eq!(vec!["Typo", "Reply"], line_ten);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::utils::parse_object_id;
+
+/// A commit message in the format that arcanist uses, for example:
+///
+/// ```text
+/// Title of the change
+///
+/// Summary of the change
+///
+/// Test Plan: How the change was tested
+///
+/// Reviewers: alice, bob
+///
+/// Differential Revision: https://phab.mycompany.com/D1234
+/// ```
+#[derive(Debug, PartialEq, Default)]
+pub struct CommitMessage {
+    pub title: String,
+    pub summary: String,
+    pub test_plan: String,
+    pub reviewers: Vec<String>,
+    pub revision_url: Option<String>,
+    original: String,
+}
+
+enum Section {
+    Summary,
+    TestPlan,
+    Reviewers,
+    Revision,
+    Other,
+}
+
+impl CommitMessage {
+    pub fn parse(message: &str) -> CommitMessage {
+        let mut lines = message.trim().lines();
+        let title = lines.next().unwrap_or_default().trim().to_string();
+
+        let mut summary = Vec::new();
+        let mut test_plan = Vec::new();
+        let mut reviewers = Vec::new();
+        let mut revision_url = None;
+        let mut section = Section::Summary;
+
+        for line in lines {
+            let content = match line.find(':') {
+                Some(index) => match line[..index].trim().to_lowercase().as_str() {
+                    "summary" => {
+                        section = Section::Summary;
+                        &line[index + 1..]
+                    }
+                    "test plan" => {
+                        section = Section::TestPlan;
+                        &line[index + 1..]
+                    }
+                    "reviewers" | "reviewer" => {
+                        section = Section::Reviewers;
+                        &line[index + 1..]
+                    }
+                    "differential revision" => {
+                        section = Section::Revision;
+                        &line[index + 1..]
+                    }
+                    "subscribers" | "cc" | "tasks" | "maniphest tasks" => {
+                        section = Section::Other;
+                        &line[index + 1..]
+                    }
+                    _ => line,
+                },
+                None => line,
+            };
+
+            match section {
+                Section::Summary => summary.push(content),
+                Section::TestPlan => test_plan.push(content.trim_start()),
+                Section::Reviewers => reviewers.extend(
+                    content
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|reviewer| !reviewer.is_empty())
+                        .map(str::to_string),
+                ),
+                Section::Revision => {
+                    if !content.trim().is_empty() {
+                        revision_url = Some(content.trim().to_string())
+                    }
+                }
+                Section::Other => {}
+            }
+        }
+
+        CommitMessage {
+            title,
+            summary: summary.join("\n").trim().to_string(),
+            test_plan: test_plan.join("\n").trim().to_string(),
+            reviewers,
+            revision_url,
+            original: message.trim().to_string(),
+        }
+    }
+
+    /// The id of the revision in the `Differential Revision:` trailer, if it belongs
+    /// to the given Phabricator instance.
+    pub fn revision_id(&self, hosted_instance: &str) -> Option<i32> {
+        self.revision_url
+            .as_ref()
+            .and_then(|url| url.strip_prefix(hosted_instance))
+            .and_then(|id| parse_object_id('D', id).ok())
+    }
+
+    /// The original commit message with a `Differential Revision:` trailer for the url.
+    pub fn with_revision_url(&self, url: &str) -> String {
+        format!("{}\n\nDifferential Revision: {}\n", self.original, url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit_message() {
+        let message = CommitMessage::parse(
+            "Add fab submit
+
+Uploads the current branch.
+Creates a revision for it.
+
+Test Plan: Ran it
+against a local instance
+
+Reviewers: alice, bob
+
+Differential Revision: https://phab.mycompany.com/D1234
+",
+        );
+
+        assert_eq!("Add fab submit", message.title);
+        assert_eq!(
+            "Uploads the current branch.\nCreates a revision for it.",
+            message.summary
+        );
+        assert_eq!("Ran it\nagainst a local instance", message.test_plan);
+        assert_eq!(vec!["alice", "bob"], message.reviewers);
+        assert_eq!(
+            Some(String::from("https://phab.mycompany.com/D1234")),
+            message.revision_url
+        );
+    }
+
+    #[test]
+    fn test_parse_title_only() {
+        let message = CommitMessage::parse("Fix typo\n");
+
+        assert_eq!("Fix typo", message.title);
+        assert_eq!("", message.summary);
+        assert_eq!("", message.test_plan);
+        assert!(message.reviewers.is_empty());
+        assert_eq!(None, message.revision_url);
+    }
+
+    #[test]
+    fn test_revision_id() {
+        let message = CommitMessage::parse(
+            "Fix typo\n\nDifferential Revision: https://phab.mycompany.com/D12",
+        );
+
+        assert_eq!(Some(12), message.revision_id("https://phab.mycompany.com/"));
+        assert_eq!(None, message.revision_id("https://phab.othercompany.com/"));
+    }
+
+    #[test]
+    fn test_with_revision_url() {
+        let message = CommitMessage::parse("Fix typo\n\nSummary: Fixes a typo\n");
+
+        assert_eq!(
+            "Fix typo\n\nSummary: Fixes a typo\n\nDifferential Revision: https://phab.mycompany.com/D1\n",
+            message.with_revision_url("https://phab.mycompany.com/D1")
+        );
+    }
+}
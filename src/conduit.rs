@@ -0,0 +1,502 @@
+use anyhow::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// The largest page Conduit hands out for a single `*.search` call.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Returns a new API token after Conduit rejected the current one.
+type RenewToken = dyn Fn() -> Result<String, Error> + Send + Sync;
+
+/// A client for the Conduit API of a Phabricator instance. Clones share the same
+/// connection pool and API token.
+#[derive(Clone)]
+pub struct ConduitClient {
+    http: reqwest::Client,
+    hosted_instance: String,
+    api_token: Arc<Mutex<String>>,
+    renew_token: Option<Arc<RenewToken>>,
+}
+
+impl ConduitClient {
+    /// Creates a client for the instance at `hosted_instance`, e.g.
+    /// `https://phab.example.com/`.
+    pub fn new(hosted_instance: &str, api_token: &str) -> ConduitClient {
+        ConduitClient::with_http_client(reqwest::Client::new(), hosted_instance, api_token)
+    }
+
+    /// Creates a client that sends its requests through an existing HTTP client.
+    pub fn with_http_client(
+        http: reqwest::Client,
+        hosted_instance: &str,
+        api_token: &str,
+    ) -> ConduitClient {
+        let mut hosted_instance = hosted_instance.to_string();
+        if !hosted_instance.ends_with('/') {
+            hosted_instance.push('/');
+        }
+
+        ConduitClient {
+            http,
+            hosted_instance,
+            api_token: Arc::new(Mutex::new(api_token.to_string())),
+            renew_token: None,
+        }
+    }
+
+    /// Asks `renew_token` for a new API token when Conduit rejects the current one,
+    /// e.g. because it expired. The rejected call is then retried once with it.
+    pub fn on_invalid_auth<F>(mut self, renew_token: F) -> ConduitClient
+    where
+        F: Fn() -> Result<String, Error> + Send + Sync + 'static,
+    {
+        self.renew_token = Some(Arc::new(renew_token));
+        self
+    }
+
+    pub fn hosted_instance(&self) -> &str {
+        &self.hosted_instance
+    }
+
+    pub fn api_token(&self) -> String {
+        self.api_token.lock().unwrap().clone()
+    }
+
+    /// Calls a Conduit method such as `user.whoami`. Errors reported by Conduit are
+    /// returned as a `ConduitError`.
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &Params,
+    ) -> Result<T, Error> {
+        let api_token = self.api_token();
+        let error = match self.send(method, params, &api_token).await {
+            Ok(result) => return Ok(result),
+            Err(error) => error,
+        };
+
+        let is_invalid_auth = matches!(
+            error.downcast_ref::<ConduitError>(),
+            Some(error) if error.is_invalid_auth()
+        );
+        let renew_token = match &self.renew_token {
+            Some(renew_token) if is_invalid_auth => renew_token,
+            _ => return Err(error),
+        };
+
+        let api_token = {
+            let mut current_token = self.api_token.lock().unwrap();
+            // Another call may have renewed the token in the meantime.
+            if *current_token == api_token {
+                *current_token = renew_token()?;
+            }
+            current_token.clone()
+        };
+
+        self.send(method, params, &api_token).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &Params,
+        api_token: &str,
+    ) -> Result<T, Error> {
+        let url = format!("{}api/{}", self.hosted_instance, method);
+
+        let mut form = params.map.clone();
+        form.insert("api.token".to_string(), Value::from(api_token));
+
+        let response = self
+            .http
+            .post(&url)
+            .form(&Value::Object(form))
+            .send()
+            .await?
+            .json::<ConduitResponse<T>>()
+            .await?;
+
+        match (response.result, response.error_code) {
+            (_, Some(error_code)) => Err(ConduitError {
+                error_code,
+                error_info: response.error_info,
+            }
+            .into()),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(ConduitError {
+                error_code: "ERR-EMPTY-RESULT".to_string(),
+                error_info: Some(format!("{} didn't return a result", method)),
+            }
+            .into()),
+        }
+    }
+
+    /// Runs a `*.search` call and keeps following the `after` cursor until `limit`
+    /// results are fetched or there are no more pages.
+    pub async fn search<T: DeserializeOwned>(
+        &self,
+        search: &Search,
+        limit: Limit,
+    ) -> Result<Vec<T>, Error> {
+        self.search_filtered(search, limit, |_| true).await
+    }
+
+    /// Like `search`, but only the results that pass `keep` are returned and count
+    /// towards the limit.
+    pub async fn search_filtered<T, F>(
+        &self,
+        search: &Search,
+        limit: Limit,
+        keep: F,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let mut results = Vec::new();
+        let mut after: Option<Value> = None;
+
+        loop {
+            let mut params = search
+                .params
+                .clone()
+                .set("limit", limit.page_size(results.len()));
+            if let Some(after) = &after {
+                params = params.set("after", after.clone());
+            }
+
+            let page = self.call::<SearchPage<T>>(&search.method, &params).await?;
+
+            results.extend(page.data.into_iter().filter(|item| keep(item)));
+
+            match page.cursor.after {
+                Some(cursor) if !limit.is_met(results.len()) => after = Some(cursor),
+                _ => break,
+            }
+        }
+
+        if let Limit::Count(count) = limit {
+            results.truncate(count);
+        }
+
+        Ok(results)
+    }
+
+    /// Applies transactions through an `*.edit` method such as `maniphest.edit`.
+    /// Without an object identifier, a new object is created.
+    pub async fn edit(
+        &self,
+        method: &str,
+        object_identifier: Option<&str>,
+        transactions: &[Transaction],
+    ) -> Result<EditResult, Error> {
+        let mut params = Params::new().transactions(transactions);
+        if let Some(object_identifier) = object_identifier {
+            params = params.set("objectIdentifier", object_identifier);
+        }
+
+        self.call::<EditResult>(method, &params).await
+    }
+}
+
+impl fmt::Debug for ConduitClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConduitClient")
+            .field("hosted_instance", &self.hosted_instance)
+            .finish()
+    }
+}
+
+/// Parameters of a Conduit call. Conduit takes them as a form, so lists are sent
+/// as `key[0]`, `key[1]` and so on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    map: Map<String, Value>,
+}
+
+impl Params {
+    pub fn new() -> Params {
+        Params::default()
+    }
+
+    pub fn set<V: Into<Value>>(mut self, key: &str, value: V) -> Params {
+        self.map.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn list<V: Into<Value> + Clone>(mut self, key: &str, values: &[V]) -> Params {
+        for (i, value) in values.iter().enumerate() {
+            self.map
+                .insert(format!("{}[{}]", key, i), value.clone().into());
+        }
+        self
+    }
+
+    /// Adds the transactions in the `transactions[i][type]` and
+    /// `transactions[i][value]` format that the `*.edit` methods expect. List
+    /// values are expanded into `transactions[i][value][j]`.
+    pub fn transactions(mut self, transactions: &[Transaction]) -> Params {
+        for (i, transaction) in transactions.iter().enumerate() {
+            self.map.insert(
+                format!("transactions[{}][type]", i),
+                Value::from(transaction.kind.clone()),
+            );
+
+            match &transaction.value {
+                Value::Array(values) => {
+                    for (j, value) in values.iter().enumerate() {
+                        self.map
+                            .insert(format!("transactions[{}][value][{}]", i, j), value.clone());
+                    }
+                }
+                value => {
+                    self.map
+                        .insert(format!("transactions[{}][value]", i), value.clone());
+                }
+            }
+        }
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.map.get(key)
+    }
+}
+
+/// A `*.search` call along with its query key, constraints, attachments and order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    method: String,
+    params: Params,
+}
+
+impl Search {
+    /// Starts a search through a method such as `differential.revision.search`.
+    pub fn new(method: &str) -> Search {
+        Search {
+            method: method.to_string(),
+            params: Params::new(),
+        }
+    }
+
+    /// Starts from a saved query, e.g. `assigned` or `authored`.
+    pub fn query_key(mut self, query_key: &str) -> Search {
+        self.params = self.params.set("queryKey", query_key);
+        self
+    }
+
+    /// Adds a constraint with a single value, like `constraints[query]`.
+    pub fn constraint<V: Into<Value>>(mut self, name: &str, value: V) -> Search {
+        self.params = self.params.set(&format!("constraints[{}]", name), value);
+        self
+    }
+
+    /// Adds a constraint with a list of values, like `constraints[statuses][0]`.
+    pub fn constraints<V: Into<Value> + Clone>(mut self, name: &str, values: &[V]) -> Search {
+        self.params = self.params.list(&format!("constraints[{}]", name), values);
+        self
+    }
+
+    /// Asks for an attachment, like `attachments[reviewers]`.
+    pub fn attachment(mut self, name: &str) -> Search {
+        self.params = self.params.set(&format!("attachments[{}]", name), true);
+        self
+    }
+
+    /// Sets a parameter that isn't a constraint, like `objectIdentifier` of
+    /// `transaction.search`.
+    pub fn param<V: Into<Value>>(mut self, key: &str, value: V) -> Search {
+        self.params = self.params.set(key, value);
+        self
+    }
+
+    pub fn order(mut self, order: &str) -> Search {
+        self.params = self.params.set("order", order);
+        self
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+}
+
+/// How many results a search should fetch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Count(usize),
+    All,
+}
+
+impl Limit {
+    fn page_size(self, fetched: usize) -> usize {
+        match self {
+            Limit::Count(count) => count.saturating_sub(fetched).clamp(1, MAX_PAGE_SIZE),
+            Limit::All => MAX_PAGE_SIZE,
+        }
+    }
+
+    fn is_met(self, fetched: usize) -> bool {
+        match self {
+            Limit::Count(count) => fetched >= count,
+            Limit::All => false,
+        }
+    }
+}
+
+/// A page of results of a `*.search` call.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SearchPage<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub cursor: Cursor,
+}
+
+/// Where a page of search results ends. `after` is passed back to Conduit to
+/// fetch the next page and is empty on the last one.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Cursor {
+    pub after: Option<Value>,
+}
+
+/// A single transaction that is applied through one of the `*.edit` methods,
+/// for example `differential.revision.edit` or `maniphest.edit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub kind: String,
+    pub value: Value,
+}
+
+impl Transaction {
+    pub fn new(kind: &str, value: Value) -> Transaction {
+        Transaction {
+            kind: kind.to_string(),
+            value,
+        }
+    }
+
+    pub fn comment(message: &str) -> Transaction {
+        Transaction::new("comment", Value::from(message))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EditResult {
+    pub object: EditedObject,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EditedObject {
+    pub id: i32,
+    pub phid: String,
+}
+
+/// An error reported by Conduit, e.g. `ERR-INVALID-AUTH`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConduitError {
+    pub error_code: String,
+    pub error_info: Option<String>,
+}
+
+impl ConduitError {
+    /// Whether the API token was rejected, for example because it was revoked.
+    pub fn is_invalid_auth(&self) -> bool {
+        self.error_code == "ERR-INVALID-AUTH" || self.error_code == "ERR-INVALID-SESSION"
+    }
+}
+
+impl fmt::Display for ConduitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error_info {
+            Some(error_info) => write!(f, "{}: {}", self.error_code, error_info),
+            None => write!(f, "{}", self.error_code),
+        }
+    }
+}
+
+impl std::error::Error for ConduitError {}
+
+#[derive(Deserialize, Debug)]
+struct ConduitResponse<T> {
+    result: Option<T>,
+    error_code: Option<String>,
+    error_info: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_params() {
+        let search = Search::new("maniphest.search")
+            .query_key("assigned")
+            .constraint("query", "crash")
+            .constraints("priorities", &[100, 90])
+            .attachment("columns")
+            .order("priority");
+
+        let params = search.params();
+        assert_eq!(Some(&json!("assigned")), params.get("queryKey"));
+        assert_eq!(Some(&json!("crash")), params.get("constraints[query]"));
+        assert_eq!(Some(&json!(100)), params.get("constraints[priorities][0]"));
+        assert_eq!(Some(&json!(90)), params.get("constraints[priorities][1]"));
+        assert_eq!(Some(&json!(true)), params.get("attachments[columns]"));
+        assert_eq!(Some(&json!("priority")), params.get("order"));
+    }
+
+    #[test]
+    fn test_transactions() {
+        let params = Params::new().transactions(&[
+            Transaction::new("accept", Value::Bool(true)),
+            Transaction::comment("Looks good"),
+            Transaction::new("reviewers.add", json!(["PHID-USER-1", "PHID-USER-2"])),
+        ]);
+
+        assert_eq!(Some(&json!("accept")), params.get("transactions[0][type]"));
+        assert_eq!(Some(&json!(true)), params.get("transactions[0][value]"));
+        assert_eq!(Some(&json!("comment")), params.get("transactions[1][type]"));
+        assert_eq!(
+            Some(&json!("Looks good")),
+            params.get("transactions[1][value]")
+        );
+        assert_eq!(
+            Some(&json!("PHID-USER-1")),
+            params.get("transactions[2][value][0]")
+        );
+        assert_eq!(
+            Some(&json!("PHID-USER-2")),
+            params.get("transactions[2][value][1]")
+        );
+        assert_eq!(None, params.get("transactions[2][value]"));
+    }
+
+    #[test]
+    fn test_page_size() {
+        assert_eq!(Limit::Count(20).page_size(0), 20);
+        assert_eq!(Limit::Count(250).page_size(0), 100);
+        assert_eq!(Limit::Count(250).page_size(200), 50);
+        assert_eq!(Limit::Count(20).page_size(20), 1);
+        assert_eq!(Limit::All.page_size(1000), 100);
+    }
+
+    #[test]
+    fn test_is_met() {
+        assert!(!Limit::Count(20).is_met(19));
+        assert!(Limit::Count(20).is_met(20));
+        assert!(!Limit::All.is_met(10_000));
+    }
+
+    #[test]
+    fn test_conduit_error() {
+        let error = ConduitError {
+            error_code: "ERR-INVALID-AUTH".to_string(),
+            error_info: Some("API token is invalid".to_string()),
+        };
+        assert!(error.is_invalid_auth());
+        assert_eq!(error.to_string(), "ERR-INVALID-AUTH: API token is invalid");
+    }
+}
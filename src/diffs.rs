@@ -1,40 +1,50 @@
-use crate::structs::{FabConfig, Revision, RevisionData};
+use crate::builds::{get_build_statuses, BuildStatus};
+use crate::comments::{get_transactions, print_comments};
+use crate::commit_message::CommitMessage;
+use crate::errors::{finish_quietly, InvalidArgument, NotFound};
+use crate::output::{self, Format, Output, Record};
+use crate::phids::Handle;
+use crate::preferences::Preferences;
+use crate::structs::{Diff, FabConfig, Revision};
+use crate::transactions::{edit_object, print_object};
+use crate::utils::{format_timestamp, open_in_browser, parse_limit, parse_object_id};
 use crate::NO_BORDER_PRESET;
-use crate::{auth, users};
+use crate::{git, phids, remarkup, users};
 use anyhow::{anyhow, Error};
 use clap::ArgMatches;
 use comfy_table::{Attribute, Cell, CellAlignment, ContentArrangement, Table};
-use serde_json::{Map, Value};
+use console::style;
+use fab::conduit::{EditResult, Limit, Search, Transaction};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use tokio::runtime::Runtime;
 
-const DIFFERENTIAL_SEARCH_URL: &str = "api/differential.revision.search";
+const DIFFERENTIAL_SEARCH: &str = "differential.revision.search";
+const DIFF_SEARCH: &str = "differential.diff.search";
+const DIFFERENTIAL_EDIT: &str = "differential.revision.edit";
+/// Status function matching every revision that isn't closed. Filtering on the
+/// server keeps paging from walking through all the closed revisions.
+const OPEN_STATUSES: &str = "open()";
 
-/// Get diffs that are authored by the user.
-pub async fn get_authored_diffs(config: &FabConfig) -> Result<Vec<Revision>, Error> {
-    let json_body = json!({
-        "queryKey": "authored",
-        "api.token": config.api_token,
-    });
+/// Get open diffs that are authored by the user.
+pub async fn get_authored_diffs(config: &FabConfig, limit: Limit) -> Result<Vec<Revision>, Error> {
+    let search = revision_search()
+        .query_key("authored")
+        .constraints("statuses", &[OPEN_STATUSES]);
 
-    let url = format!(
-        "{}{}",
-        &config.hosted_instance,
-        DIFFERENTIAL_SEARCH_URL.to_string()
-    );
-
-    let result =
-        auth::send::<RevisionData>(config, reqwest::Client::new().post(&url).form(&json_body))
-            .await?
-            .data
-            .into_iter()
-            .filter(|rev| !rev.fields.status.closed)
-            .collect();
-
-    Ok(result)
+    config
+        .client()
+        .search_filtered(&search, limit, is_open)
+        .await
 }
 
 /// Get diffs authored by given author
-pub async fn get_diffs(config: &FabConfig, author: &Option<&str>) -> Result<Vec<Revision>, Error> {
+pub async fn get_diffs(
+    config: &FabConfig,
+    author: &Option<&str>,
+    limit: Limit,
+) -> Result<Vec<Revision>, Error> {
     if author.is_none() {
         return Err(anyhow!("No author specified"));
     }
@@ -43,56 +53,188 @@ pub async fn get_diffs(config: &FabConfig, author: &Option<&str>) -> Result<Vec<
 
     let user = users::get_user(&author, config).await?;
 
-    let url = format!(
-        "{}{}",
-        &config.hosted_instance,
-        DIFFERENTIAL_SEARCH_URL.to_string()
-    );
+    let search = revision_search()
+        .constraints("authorPHIDs", &[user.phid])
+        .constraints("statuses", &[OPEN_STATUSES]);
 
-    let mut map = Map::new();
-    map.insert(
-        "api.token".to_string(),
-        Value::from(config.api_token.clone()),
-    );
-    map.insert(
-        "constraints[authorPHIDs][0]".to_string(),
-        Value::from(user.phid.clone()),
-    );
+    config
+        .client()
+        .search_filtered(&search, limit, is_open)
+        .await
+}
 
-    let json_body = Value::Object(map);
+/// Get the diffs that needs review from the user.
+pub async fn get_needs_review_diffs(
+    config: &FabConfig,
+    limit: Limit,
+) -> Result<Vec<Revision>, Error> {
+    let search = revision_search()
+        .constraints("reviewerPHIDs", &[config.phid.as_str()])
+        .constraints("statuses", &[OPEN_STATUSES]);
 
-    let result =
-        auth::send::<RevisionData>(config, reqwest::Client::new().post(&url).form(&json_body))
-            .await?
-            .data
-            .into_iter()
-            .filter(|rev| !rev.fields.status.closed)
-            .collect();
+    config
+        .client()
+        .search_filtered(&search, limit, is_open)
+        .await
+}
+
+/// Get the revisions of a saved query of the Phabricator web UI, like `active`
+/// or `abc123XYZ`. The saved query decides which statuses are included.
+pub async fn query_diffs(
+    config: &FabConfig,
+    query_key: &str,
+    limit: Limit,
+) -> Result<Vec<Revision>, Error> {
+    let search = revision_search().query_key(query_key);
+
+    config.client().search(&search, limit).await
+}
+
+/// Get revisions of anyone that match the full-text query.
+pub async fn search_diffs(
+    query: &str,
+    limit: Limit,
+    config: &FabConfig,
+) -> Result<Vec<Revision>, Error> {
+    let search = revision_search().constraint("query", query);
+
+    config.client().search(&search, limit).await
+}
+
+/// Searches revisions along with their reviewers and subscribers, which are part
+/// of `RevisionRecord`.
+fn revision_search() -> Search {
+    Search::new(DIFFERENTIAL_SEARCH)
+        .attachment("reviewers")
+        .attachment("subscribers")
+}
+
+fn is_open(revision: &Revision) -> bool {
+    !revision.fields.status.closed
+}
+
+/// Get a single revision along with its reviewers and subscribers.
+pub async fn get_revision(config: &FabConfig, id: i32) -> Result<Revision, Error> {
+    let search = revision_search().constraints("ids", &[id]);
+
+    config
+        .client()
+        .search::<Revision>(&search, Limit::Count(1))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| NotFound(format!("Could not find D{}", id)).into())
+}
+
+/// Get a single revision as printed with `--format`, e.g. after it was edited.
+pub async fn get_revision_record(config: &FabConfig, id: i32) -> Result<RevisionRecord, Error> {
+    let revision = get_revision(config, id).await?;
+    let build_statuses = get_build_statuses(config, &[&revision]).await;
+
+    Ok(RevisionRecord::new(
+        config,
+        &revision,
+        build_statuses.get(&revision.fields.diff_phid),
+    ))
+}
+
+/// Get the diff with the given PHID. Used to figure out details like the branch
+/// of a revision that aren't available on the revision itself.
+pub async fn get_diff(config: &FabConfig, diff_phid: &str) -> Result<Option<Diff>, Error> {
+    let search = Search::new(DIFF_SEARCH).constraints("phids", &[diff_phid]);
+
+    let result = config
+        .client()
+        .search::<Diff>(&search, Limit::Count(1))
+        .await?
+        .into_iter()
+        .next();
 
     Ok(result)
 }
 
-/// Get the diffs that needs review from the user.
-pub async fn get_needs_review_diffs(config: &FabConfig) -> Result<Vec<Revision>, Error> {
-    let json_body = json!({
-        "api.token": config.api_token,
-        "constraints[reviewerPHIDs][0]": config.phid
-    });
-
-    let url = format!("{}{}", config.hosted_instance, DIFFERENTIAL_SEARCH_URL);
-
-    let result =
-        auth::send::<RevisionData>(config, reqwest::Client::new().post(&url).form(&json_body))
-            .await?
-            .data
-            .into_iter()
-            .filter(|rev| !rev.fields.status.closed)
-            .collect();
+/// Get the diff with the given id if it belongs to the revision.
+pub async fn get_revision_diff(
+    config: &FabConfig,
+    revision_phid: &str,
+    diff_id: i32,
+) -> Result<Option<Diff>, Error> {
+    let search = Search::new(DIFF_SEARCH)
+        .constraints("ids", &[diff_id])
+        .constraints("revisionPHIDs", &[revision_phid]);
+
+    let result = config
+        .client()
+        .search::<Diff>(&search, Limit::Count(1))
+        .await?
+        .into_iter()
+        .next();
 
     Ok(result)
 }
 
-pub fn render_diffs(config: &FabConfig, revisions: &[Revision]) {
+/// Apply the given transactions to a revision.
+pub async fn edit_revision(
+    config: &FabConfig,
+    id: i32,
+    transactions: &[Transaction],
+) -> Result<EditResult, Error> {
+    edit_revision_with_identifier(config, Some(&format!("D{}", id)), transactions).await
+}
+
+/// Apply the given transactions to the revision with the given identifier, or
+/// create a new revision if there is none.
+pub async fn edit_revision_with_identifier(
+    config: &FabConfig,
+    object_identifier: Option<&str>,
+    transactions: &[Transaction],
+) -> Result<EditResult, Error> {
+    config
+        .client()
+        .edit(DIFFERENTIAL_EDIT, object_identifier, transactions)
+        .await
+}
+
+/// Prints the revisions as the default table, or as chosen with `--format`,
+/// `--columns` or `--template`.
+pub fn print_diffs(
+    output: &Output,
+    config: &FabConfig,
+    revisions: &[Revision],
+    build_statuses: &HashMap<String, BuildStatus>,
+) -> Result<(), Error> {
+    if output.is_default_table() {
+        render_diffs(config, revisions, build_statuses);
+        return Ok(());
+    }
+
+    output.print(&revision_records(config, revisions, build_statuses))
+}
+
+/// The revisions as records for `--format`, along with the build status of their
+/// latest diff.
+pub fn revision_records(
+    config: &FabConfig,
+    revisions: &[Revision],
+    build_statuses: &HashMap<String, BuildStatus>,
+) -> Vec<RevisionRecord> {
+    revisions
+        .iter()
+        .map(|revision| {
+            RevisionRecord::new(
+                config,
+                revision,
+                build_statuses.get(&revision.fields.diff_phid),
+            )
+        })
+        .collect()
+}
+
+pub fn render_diffs(
+    config: &FabConfig,
+    revisions: &[Revision],
+    build_statuses: &HashMap<String, BuildStatus>,
+) {
     let mut table = Table::new();
 
     table
@@ -100,12 +242,14 @@ pub fn render_diffs(config: &FabConfig, revisions: &[Revision]) {
         .set_content_arrangement(ContentArrangement::Dynamic);
 
     for revision in revisions {
+        let build_status = match build_statuses.get(&revision.fields.diff_phid) {
+            Some(status) => status.cell(),
+            None => Cell::new(""),
+        };
+
         table.add_row(vec![
-            Cell::new(&revision.fields.status.name)
-                .bg(revision.get_background())
-                .fg(revision.get_foreground())
-                .set_alignment(CellAlignment::Center)
-                .add_attribute(Attribute::Bold),
+            revision.get_status_cell(),
+            build_status,
             Cell::new(&revision.fields.title),
             Cell::new(&revision.url(config)).add_attribute(Attribute::Bold),
         ]);
@@ -113,32 +257,461 @@ pub fn render_diffs(config: &FabConfig, revisions: &[Revision]) {
     println!("{}", table);
 }
 
-pub fn process_diff_command(_matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
-    if _matches.is_present("needs-review") {
-        process_diffs_needs_review(config)?;
-        return Ok(());
+pub fn process_diff_command(
+    matches: &ArgMatches,
+    config: &FabConfig,
+    preferences: &Preferences,
+) -> Result<(), Error> {
+    let limit = parse_limit(matches)?;
+    let output = Output::from_matches(matches, preferences.default_columns.get("diffs"))?;
+
+    let mut runtime = Runtime::new()?;
+    let revisions = if let Some(query_key) = matches.value_of("query-key") {
+        runtime.block_on(query_diffs(config, query_key, limit))?
+    } else if matches.is_present("needs-review") {
+        runtime.block_on(get_needs_review_diffs(config, limit))?
+    } else if matches.is_present("author") {
+        runtime.block_on(get_diffs(config, &matches.value_of("author"), limit))?
+    } else {
+        runtime.block_on(get_authored_diffs(config, limit))?
+    };
+
+    if output.quiet {
+        return finish_quietly(&revisions);
     }
-    if _matches.is_present("author") {
-        process_authored_diffs(config, _matches.value_of("author"))?;
-        return Ok(());
+
+    let build_statuses = runtime.block_on(get_build_statuses(
+        config,
+        &revisions.iter().collect::<Vec<_>>(),
+    ));
+
+    print_diffs(&output, config, &revisions, &build_statuses)
+}
+
+pub fn render_diff_detail(
+    config: &FabConfig,
+    revision: &Revision,
+    diff: &Option<Diff>,
+    handles: &HashMap<String, Handle>,
+) {
+    println!(
+        "{}",
+        style(format!("D{}: {}", revision.id, revision.fields.title)).bold()
+    );
+    println!();
+
+    let mut table = Table::new();
+
+    table
+        .load_preset(NO_BORDER_PRESET)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new("Status").add_attribute(Attribute::Bold),
+        revision.get_status_cell(),
+    ]);
+    table.add_row(vec![
+        Cell::new("Author").add_attribute(Attribute::Bold),
+        Cell::new(phids::get_name(handles, &revision.fields.author_phid)),
+    ]);
+    if let Some(repository_phid) = &revision.fields.repository_phid {
+        table.add_row(vec![
+            Cell::new("Repository").add_attribute(Attribute::Bold),
+            Cell::new(phids::get_name(handles, repository_phid)),
+        ]);
+    }
+    if let Some(branch) = diff.as_ref().and_then(|diff| diff.branch()) {
+        table.add_row(vec![
+            Cell::new("Branch").add_attribute(Attribute::Bold),
+            Cell::new(branch),
+        ]);
+    }
+    table.add_row(vec![
+        Cell::new("Created").add_attribute(Attribute::Bold),
+        Cell::new(format_timestamp(revision.fields.date_created)),
+    ]);
+    table.add_row(vec![
+        Cell::new("Modified").add_attribute(Attribute::Bold),
+        Cell::new(format_timestamp(revision.fields.date_modified)),
+    ]);
+    table.add_row(vec![
+        Cell::new("URL").add_attribute(Attribute::Bold),
+        Cell::new(&revision.url(config)).add_attribute(Attribute::Bold),
+    ]);
+    println!("{}", table);
+    println!();
+
+    if let Some(reviewers) = &revision.attachments.reviewers {
+        println!("{}", style("Reviewers").bold().underlined());
+        println!();
+
+        let mut table = Table::new();
+
+        table
+            .load_preset(NO_BORDER_PRESET)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+
+        for reviewer in &reviewers.reviewers {
+            let blocking = if reviewer.is_blocking { "Blocking" } else { "" };
+            table.add_row(vec![
+                Cell::new(&reviewer.status)
+                    .bg(reviewer.get_background())
+                    .fg(reviewer.get_foreground())
+                    .set_alignment(CellAlignment::Center)
+                    .add_attribute(Attribute::Bold),
+                Cell::new(phids::get_name(handles, &reviewer.reviewer_phid)),
+                Cell::new(blocking),
+            ]);
+        }
+        println!("{}", table);
+        println!();
     }
 
-    let result = Runtime::new()?.block_on(get_authored_diffs(config))?;
+    if let Some(subscribers) = &revision.attachments.subscribers {
+        if !subscribers.subscriber_phids.is_empty() {
+            let names: Vec<String> = subscribers
+                .subscriber_phids
+                .iter()
+                .map(|phid| phids::get_name(handles, phid))
+                .collect();
 
-    render_diffs(config, &result);
+            println!("{}", style("Subscribers").bold().underlined());
+            println!();
+            println!("{}", names.join(", "));
+            println!();
+        }
+    }
+
+    if !revision.fields.summary.is_empty() {
+        println!("{}", style("Summary").bold().underlined());
+        println!();
+        println!("{}", remarkup::render(&revision.fields.summary));
+        println!();
+    }
+
+    if !revision.fields.test_plan.is_empty() {
+        println!("{}", style("Test Plan").bold().underlined());
+        println!();
+        println!("{}", remarkup::render(&revision.fields.test_plan));
+        println!();
+    }
+}
+
+/// Shows the details of a single revision given by `fab diff <ID>`. Without an ID,
+/// the revision of the current git branch is shown.
+pub fn process_single_diff_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = match matches.value_of("id") {
+        Some(id) => parse_object_id('D', id)?,
+        None => find_current_revision(config)?,
+    };
+
+    if matches.is_present("open") {
+        return open_in_browser(&format!("{}D{}", config.hosted_instance, id));
+    }
+
+    let mut runtime = Runtime::new()?;
+    let format = Format::from_matches(matches)?;
+
+    if matches.is_present("comments") {
+        let transactions = runtime.block_on(get_transactions(config, &format!("D{}", id)))?;
+
+        let mut authors: Vec<String> = transactions
+            .iter()
+            .map(|transaction| transaction.author_phid.clone())
+            .collect();
+        authors.sort();
+        authors.dedup();
+
+        let handles = runtime.block_on(phids::get_handles(&authors, config))?;
+
+        return print_comments(format, &transactions, &handles);
+    }
+
+    if format != Format::Table {
+        return output::print_record(format, &runtime.block_on(get_revision_record(config, id))?);
+    }
+
+    let revision = runtime.block_on(get_revision(config, id))?;
+
+    let diff = runtime.block_on(get_diff(config, &revision.fields.diff_phid))?;
+    let handles = runtime.block_on(phids::get_handles(&related_phids(&revision), config))?;
+
+    render_diff_detail(config, &revision, &diff, &handles);
+    Ok(())
+}
+
+/// Finds the revision of the current branch through the `Differential Revision:`
+/// trailer of the commits that aren't on `origin`'s default branch yet, or of HEAD
+/// when there is no such branch.
+fn find_current_revision(config: &FabConfig) -> Result<i32, Error> {
+    let not_found = || {
+        NotFound(
+            "There is no revision for this branch. Pass the revision instead, e.g. `fab diff D1234`"
+                .to_string(),
+        )
+    };
+
+    if !git::is_repository() {
+        return Err(not_found().into());
+    }
+
+    // Only the commits of the branch, so that a revision that already landed isn't picked up.
+    let messages = match git::default_base() {
+        Some(base) => git::branch_commit_messages(&base)?,
+        None => git::head_commit_message().into_iter().collect(),
+    };
+
+    messages
+        .iter()
+        .find_map(|message| CommitMessage::parse(message).revision_id(&config.hosted_instance))
+        .ok_or_else(|| not_found().into())
+}
+
+/// Collects the PHIDs of all users and objects that are shown in the detail view.
+fn related_phids(revision: &Revision) -> Vec<String> {
+    let mut related = vec![revision.fields.author_phid.clone()];
+
+    if let Some(repository_phid) = &revision.fields.repository_phid {
+        related.push(repository_phid.clone());
+    }
+    if let Some(reviewers) = &revision.attachments.reviewers {
+        related.extend(
+            reviewers
+                .reviewers
+                .iter()
+                .map(|reviewer| reviewer.reviewer_phid.clone()),
+        );
+    }
+    if let Some(subscribers) = &revision.attachments.subscribers {
+        related.extend(subscribers.subscriber_phids.iter().cloned());
+    }
+
+    related.sort();
+    related.dedup();
+    related
+}
+
+/// Performs a review action such as `fab review D1234 accept` on a revision.
+pub fn process_review_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = parse_object_id('D', matches.value_of("id").unwrap())?;
+    let action = matches.value_of("action").unwrap();
+    let format = Format::from_matches(matches)?;
+
+    let mut transactions = vec![Transaction::new(
+        get_transaction_type(action)?,
+        Value::Bool(true),
+    )];
+
+    if let Some(message) = matches.value_of("message") {
+        transactions.push(Transaction::comment(message));
+    }
+
+    let mut runtime = Runtime::new()?;
+    let result = runtime.block_on(edit_revision(config, id, &transactions))?;
+
+    if format != Format::Table {
+        let record = runtime.block_on(get_revision_record(config, result.object.id))?;
+        return output::print_record(format, &record);
+    }
+
+    println!(
+        "Done! {} D{}: {}D{}",
+        style(action).bold(),
+        result.object.id,
+        config.hosted_instance,
+        result.object.id
+    );
     Ok(())
 }
 
-fn process_diffs_needs_review(config: &FabConfig) -> Result<(), Error> {
-    let revisions = Runtime::new()?.block_on(get_needs_review_diffs(config))?;
+/// Adds or removes reviewers of a revision, e.g. `fab reviewers D1234 add alice --blocking`.
+pub fn process_reviewers_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = parse_object_id('D', matches.value_of("id").unwrap())?;
+    let action = matches.value_of("action").unwrap();
+    let usernames: Vec<&str> = matches.values_of("users").unwrap().collect();
+    let format = Format::from_matches(matches)?;
+
+    if action == "remove" && matches.is_present("blocking") {
+        return Err(InvalidArgument("--blocking can only be used with `add`".to_string()).into());
+    }
+
+    let mut runtime = Runtime::new()?;
+
+    let mut reviewers = Vec::with_capacity(usernames.len());
+    for username in &usernames {
+        let user = runtime.block_on(users::get_user(username, config))?;
+        if matches.is_present("blocking") {
+            reviewers.push(Value::from(format!("blocking({})", user.phid)));
+        } else {
+            reviewers.push(Value::from(user.phid));
+        }
+    }
+
+    let kind = match action {
+        "add" => "reviewers.add",
+        "remove" => "reviewers.remove",
+        _ => return Err(anyhow!("Unknown reviewers action {}", action)),
+    };
+
+    runtime.block_on(edit_revision(
+        config,
+        id,
+        &[Transaction::new(kind, Value::Array(reviewers))],
+    ))?;
+
+    if format != Format::Table {
+        return output::print_record(format, &runtime.block_on(get_revision_record(config, id))?);
+    }
 
-    render_diffs(config, &revisions);
+    let verb = if action == "add" { "Added" } else { "Removed" };
+    println!(
+        "{} {} as reviewers of {}D{}",
+        verb,
+        usernames.join(", "),
+        config.hosted_instance,
+        id
+    );
     Ok(())
 }
 
-fn process_authored_diffs(config: &FabConfig, author: Option<&str>) -> Result<(), Error> {
-    let revisions = Runtime::new()?.block_on(get_diffs(config, &author))?;
+/// Subscribes users to a revision or a task. Subscribes yourself if no users are given.
+pub fn process_subscribe_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let object = matches.value_of("object").unwrap();
+    let format = Format::from_matches(matches)?;
+
+    let mut runtime = Runtime::new()?;
+
+    let subscribers = match matches.values_of("users") {
+        Some(usernames) => {
+            let mut subscribers = vec![];
+            for username in usernames {
+                let user = runtime.block_on(users::get_user(username, config))?;
+                subscribers.push(Value::from(user.phid));
+            }
+            subscribers
+        }
+        None => vec![Value::from(config.phid.clone())],
+    };
 
-    render_diffs(config, &revisions);
+    let url = runtime.block_on(edit_object(
+        config,
+        object,
+        &[Transaction::new(
+            "subscribers.add",
+            Value::Array(subscribers),
+        )],
+    ))?;
+
+    if format != Format::Table {
+        return runtime.block_on(print_object(format, config, object));
+    }
+
+    println!("Subscribed to {}", url);
     Ok(())
 }
+
+/// Maps the review action given on the command line to the transaction type of
+/// `differential.revision.edit`.
+fn get_transaction_type(action: &str) -> Result<&'static str, Error> {
+    match action {
+        "accept" => Ok("accept"),
+        // Phabricator calls "Request Changes" a rejection.
+        "reject" | "request-changes" => Ok("reject"),
+        "resign" => Ok("resign"),
+        "plan-changes" => Ok("plan-changes"),
+        "abandon" => Ok("abandon"),
+        "reclaim" => Ok("reclaim"),
+        "reopen" => Ok("reopen"),
+        "commandeer" => Ok("commandeer"),
+        _ => Err(anyhow!("Unknown review action {}", action)),
+    }
+}
+
+/// A revision as printed with `--format`. See `output::Record`.
+#[derive(Debug, Serialize)]
+pub struct RevisionRecord {
+    pub id: i32,
+    pub phid: String,
+    pub title: String,
+    pub status: String,
+    pub closed: bool,
+    pub build_status: Option<String>,
+    pub summary: String,
+    pub test_plan: String,
+    pub author_phid: String,
+    pub repository_phid: Option<String>,
+    pub diff_phid: String,
+    pub url: String,
+    pub date_created: i64,
+    pub date_modified: i64,
+    pub reviewer_phids: Vec<String>,
+    pub subscriber_phids: Vec<String>,
+}
+
+impl RevisionRecord {
+    pub fn new(
+        config: &FabConfig,
+        revision: &Revision,
+        build_status: Option<&BuildStatus>,
+    ) -> RevisionRecord {
+        RevisionRecord {
+            id: revision.id,
+            phid: revision.phid.clone(),
+            title: revision.fields.title.clone(),
+            status: revision.fields.status.name.clone(),
+            closed: revision.fields.status.closed,
+            build_status: build_status.map(|status| status.value.clone()),
+            summary: revision.fields.summary.clone(),
+            test_plan: revision.fields.test_plan.clone(),
+            author_phid: revision.fields.author_phid.clone(),
+            repository_phid: revision.fields.repository_phid.clone(),
+            diff_phid: revision.fields.diff_phid.clone(),
+            url: revision.url(config),
+            date_created: revision.fields.date_created,
+            date_modified: revision.fields.date_modified,
+            reviewer_phids: revision
+                .attachments
+                .reviewers
+                .iter()
+                .flat_map(|reviewers| &reviewers.reviewers)
+                .map(|reviewer| reviewer.reviewer_phid.clone())
+                .collect(),
+            subscriber_phids: revision
+                .attachments
+                .subscribers
+                .iter()
+                .flat_map(|subscribers| subscribers.subscriber_phids.iter().cloned())
+                .collect(),
+        }
+    }
+}
+
+impl Record for RevisionRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "phid",
+        "title",
+        "status",
+        "closed",
+        "build_status",
+        "summary",
+        "test_plan",
+        "author_phid",
+        "repository_phid",
+        "diff_phid",
+        "url",
+        "date_created",
+        "date_modified",
+        "reviewer_phids",
+        "subscriber_phids",
+    ];
+
+    fn cell(&self, field: &str, text: String) -> Cell {
+        match field {
+            "status" => Revision::status_cell(&text),
+            "build_status" if !text.is_empty() => BuildStatus { value: text }.cell(),
+            "url" => Cell::new(text).add_attribute(Attribute::Bold),
+            _ => Cell::new(text),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::structs::FabConfig;
+use anyhow::Error;
+use fab::conduit::{Params, SearchPage};
+use serde::Deserialize;
+
+const EDGE_SEARCH: &str = "edge.search";
+
+pub const TASK_PARENT: &str = "task.parent";
+pub const TASK_SUBTASK: &str = "task.subtask";
+pub const TASK_REVISION: &str = "task.revision";
+
+/// Get the edges of the given types that start at any of the source PHIDs.
+pub async fn get_edges(
+    config: &FabConfig,
+    source_phids: &[String],
+    types: &[&str],
+) -> Result<Vec<Edge>, Error> {
+    if source_phids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let params = Params::new()
+        .list("sourcePHIDs", source_phids)
+        .list("types", types);
+
+    let result = config
+        .client()
+        .call::<SearchPage<Edge>>(EDGE_SEARCH, &params)
+        .await?;
+
+    Ok(result.data)
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Edge {
+    #[serde(rename = "sourcePHID")]
+    pub source_phid: String,
+    #[serde(rename = "edgeType")]
+    pub edge_type: String,
+    #[serde(rename = "destinationPHID")]
+    pub destination_phid: String,
+}
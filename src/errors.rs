@@ -0,0 +1,113 @@
+use crate::auth::SetupRequired;
+use anyhow::Error;
+use fab::conduit::ConduitError;
+use std::{fmt, process};
+
+/// `--quiet` found results, e.g. revisions that need your review.
+pub const RESULTS_FOUND: i32 = 1;
+/// The command line is invalid, e.g. an unknown option or a malformed id.
+pub const INVALID_ARGUMENTS: i32 = 2;
+/// Fab couldn't authenticate, e.g. because the API token expired.
+pub const AUTH_FAILURE: i32 = 3;
+/// Phabricator couldn't be reached.
+pub const NETWORK_FAILURE: i32 = 4;
+/// A revision, task, user, project or saved query doesn't exist.
+pub const NOT_FOUND: i32 = 5;
+/// Conduit returned an error or a response fab doesn't understand.
+pub const CONDUIT_ERROR: i32 = 6;
+/// Any other failure, e.g. a failing git command.
+pub const OTHER_FAILURE: i32 = 7;
+
+/// Returned when an object that was asked for doesn't exist.
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Returned for arguments clap can't validate by itself, e.g. an id like `X12`.
+#[derive(Debug)]
+pub struct InvalidArgument(pub String);
+
+impl fmt::Display for InvalidArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidArgument {}
+
+/// Ends a `--quiet` listing. Fab exits with `RESULTS_FOUND` when there are any
+/// results and succeeds otherwise.
+pub fn finish_quietly<T>(results: &[T]) -> Result<(), Error> {
+    if !results.is_empty() {
+        process::exit(RESULTS_FOUND);
+    }
+    Ok(())
+}
+
+/// The exit code fab ends with after the error. Scripts rely on these, so they
+/// must never change.
+pub fn exit_code(error: &Error) -> i32 {
+    if let Some(error) = error.downcast_ref::<ConduitError>() {
+        return if error.is_invalid_auth() {
+            AUTH_FAILURE
+        } else {
+            CONDUIT_ERROR
+        };
+    }
+
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return if error.is_decode() {
+            CONDUIT_ERROR
+        } else {
+            NETWORK_FAILURE
+        };
+    }
+
+    if error.is::<SetupRequired>() {
+        AUTH_FAILURE
+    } else if error.is::<NotFound>() {
+        NOT_FOUND
+    } else if error.is::<InvalidArgument>() || error.is::<clap::Error>() {
+        INVALID_ARGUMENTS
+    } else {
+        OTHER_FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_exit_code() {
+        let invalid_auth = ConduitError {
+            error_code: "ERR-INVALID-AUTH".to_string(),
+            error_info: None,
+        };
+        let conduit_error = ConduitError {
+            error_code: "ERR-CONDUIT-CORE".to_string(),
+            error_info: Some("Unknown constraint".to_string()),
+        };
+
+        assert_eq!(exit_code(&invalid_auth.into()), AUTH_FAILURE);
+        assert_eq!(exit_code(&SetupRequired.into()), AUTH_FAILURE);
+        assert_eq!(exit_code(&conduit_error.into()), CONDUIT_ERROR);
+        assert_eq!(
+            exit_code(&NotFound("Could not find T12".to_string()).into()),
+            NOT_FOUND
+        );
+        assert_eq!(
+            exit_code(&InvalidArgument("'X12' is not a valid T-prefixed id".to_string()).into()),
+            INVALID_ARGUMENTS
+        );
+        assert_eq!(exit_code(&anyhow!("Couldn't run git")), OTHER_FAILURE);
+    }
+}
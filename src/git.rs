@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Error};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs git with the given arguments in the current directory and returns its output.
+pub fn run(args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|err| anyhow!("Couldn't run git: {}", err))?;
+
+    into_stdout(args, output)
+}
+
+/// Runs git with the given arguments and writes `input` to its stdin.
+pub fn run_with_input(args: &[&str], input: &str) -> Result<String, Error> {
+    let mut child = Command::new("git")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| anyhow!("Couldn't run git: {}", err))?;
+
+    child
+        .stdin
+        .take()
+        .expect("stdin of git is piped")
+        .write_all(input.as_bytes())?;
+
+    into_stdout(args, child.wait_with_output()?)
+}
+
+fn into_stdout(args: &[&str], output: Output) -> Result<String, Error> {
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn current_branch() -> Result<String, Error> {
+    Ok(run(&["rev-parse", "--abbrev-ref", "HEAD"])?
+        .trim()
+        .to_string())
+}
+
+/// The diff of HEAD against the merge base with `base`, with full context so that
+/// it can be expanded in the web UI.
+pub fn diff_against(base: &str) -> Result<String, Error> {
+    run(&[
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "-M",
+        "-U32767",
+        &format!("{}...HEAD", base),
+    ])
+}
+
+pub fn head_commit_message() -> Result<String, Error> {
+    run(&["log", "-1", "--format=%B"])
+}
+
+/// Whether the current directory is inside a git repository.
+pub fn is_repository() -> bool {
+    run(&["rev-parse", "--git-dir"]).is_ok()
+}
+
+/// The default branch of `origin`, e.g. `origin/main`. When the remote's HEAD
+/// isn't known, this is `origin/main` or `origin/master`, whichever exists.
+pub fn default_base() -> Option<String> {
+    if let Ok(base) = run(&["rev-parse", "--abbrev-ref", "origin/HEAD"]) {
+        return Some(base.trim().to_string());
+    }
+
+    ["origin/main", "origin/master"]
+        .iter()
+        .find(|base| ref_exists(&format!("refs/remotes/{}", base)))
+        .map(|base| base.to_string())
+}
+
+/// The messages of the commits on HEAD that aren't on `base`, newest first.
+pub fn branch_commit_messages(base: &str) -> Result<Vec<String>, Error> {
+    let output = run(&["log", &format!("{}..HEAD", base), "--format=%B%x00"])?;
+
+    Ok(output
+        .split('\0')
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+pub fn amend_commit_message(message: &str) -> Result<(), Error> {
+    run(&["commit", "--amend", "--no-verify", "--message", message])?;
+    Ok(())
+}
+
+pub fn branch_exists(branch: &str) -> bool {
+    ref_exists(&format!("refs/heads/{}", branch))
+}
+
+fn ref_exists(reference: &str) -> bool {
+    run(&["rev-parse", "--verify", "--quiet", reference]).is_ok()
+}
+
+pub fn checkout_new_branch(branch: &str) -> Result<(), Error> {
+    run(&["checkout", "-b", branch])?;
+    Ok(())
+}
+
+/// Fails like `apply` would, without changing the working copy.
+pub fn check_apply(patch: &str) -> Result<(), Error> {
+    run_with_input(&["apply", "--check", "--index", "-"], patch)?;
+    Ok(())
+}
+
+/// Applies the patch to the working copy and stages the changes.
+pub fn apply(patch: &str) -> Result<(), Error> {
+    run_with_input(&["apply", "--index", "--whitespace=nowarn", "-"], patch)?;
+    Ok(())
+}
+
+pub fn commit(message: &str) -> Result<(), Error> {
+    run(&["commit", "--no-verify", "--message", message])?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Fab is a command line client for Phabricator. Besides the `fab` binary, this
+//! crate exposes the typed Conduit client it is built on, so that other tools can
+//! talk to Phabricator the same way.
+//!
+//! ```no_run
+//! use fab::conduit::{ConduitClient, Limit, Search};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Task {
+//!     id: i32,
+//! }
+//!
+//! # async fn example() -> Result<(), anyhow::Error> {
+//! let client = ConduitClient::new("https://phab.example.com/", "api-token");
+//! let search = Search::new("maniphest.search")
+//!     .query_key("assigned")
+//!     .constraints("statuses", &["open"]);
+//! let tasks: Vec<Task> = client.search(&search, Limit::Count(10)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod conduit;
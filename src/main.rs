@@ -2,40 +2,112 @@
 extern crate serde_json;
 
 use crate::preferences::Preferences;
+use crate::structs::FabConfig;
 use anyhow::{anyhow, Error};
+use clap::ArgMatches;
 use clap_generate::generate;
 use clap_generate::generators::{Bash, Elvish, Fish, PowerShell, Zsh};
-use std::io;
+use std::{io, process};
 mod auth;
+mod board;
+mod builds;
 mod cli;
+mod comments;
+mod commit_message;
 mod diffs;
+mod edges;
+mod errors;
+mod git;
+mod output;
+mod patch;
+mod phids;
 mod preferences;
+mod profiles;
+mod projects;
+mod queries;
+mod remarkup;
+mod search;
 mod structs;
+mod submit;
 mod summary;
 mod tasks;
+mod transactions;
 mod users;
+mod utils;
 
-const WHO_AM_I: &str = "api/user.whoami";
 /// Preset for comfy-table so that it styles the table for no borders
 const NO_BORDER_PRESET: &str = "                     ";
 
-fn main() -> Result<(), Error> {
+fn main() {
+    if let Err(error) = try_main() {
+        match error.downcast_ref::<clap::Error>() {
+            // Help and the version come through as errors as well.
+            Some(error) if !error.use_stderr() => error.exit(),
+            Some(error) => eprintln!("{}", error),
+            None => eprintln!("Error: {:?}", error),
+        }
+        process::exit(errors::exit_code(&error));
+    }
+}
+
+fn try_main() -> Result<(), Error> {
     let preferences = preferences::get_preferences()?;
 
     // Migrate any newer fields, assign them values and store them
     let preferences = migrate_preferences(preferences)?;
 
     let app = cli::build_cli(&preferences);
-    let matches = &app.get_matches();
+    let matches = &app.try_get_matches()?;
 
-    let config = auth::init()?;
+    // Profiles are managed before one of them is loaded, so that a missing one can be added.
+    if let Some(matches) = matches.subcommand_matches("profile") {
+        return profiles::process_profile_command(matches);
+    }
 
+    let config = auth::init(
+        !matches.is_present("non-interactive"),
+        matches.value_of("profile"),
+    )?;
+
+    run(matches, &config, &preferences)
+}
+
+/// Runs the subcommand given on the command line.
+fn run(matches: &ArgMatches, config: &FabConfig, preferences: &Preferences) -> Result<(), Error> {
     if let Some(matches) = matches.subcommand_matches("diffs") {
-        diffs::process_diff_command(matches, &config)?
+        diffs::process_diff_command(matches, config, preferences)?
+    } else if let Some(matches) = matches.subcommand_matches("diff") {
+        diffs::process_single_diff_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("review") {
+        diffs::process_review_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("reviewers") {
+        diffs::process_reviewers_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("subscribe") {
+        diffs::process_subscribe_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("comment") {
+        comments::process_comment_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("submit") {
+        submit::process_submit_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("patch") {
+        patch::process_patch_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("builds") {
+        builds::process_builds_command(matches, config)?
     } else if let Some(matches) = matches.subcommand_matches("tasks") {
-        tasks::process_task_command(matches, &config, &preferences)?
+        tasks::process_task_command(matches, config, preferences)?
+    } else if let Some(matches) = matches.subcommand_matches("task") {
+        tasks::process_single_task_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("board") {
+        board::process_board_command(matches, config)?
+    } else if let Some(matches) = matches.subcommand_matches("search") {
+        search::process_search_command(matches, config, preferences)?
+    } else if let Some(matches) = matches.subcommand_matches("query") {
+        queries::process_query_command(matches, preferences)?
+    } else if let Some(matches) = matches.subcommand_matches("q") {
+        let args = queries::get_query_args(matches, preferences)?;
+        let matches = cli::build_cli(preferences).try_get_matches_from(args)?;
+        run(&matches, config, preferences)?
     } else if let Some(matches) = matches.subcommand_matches("summary") {
-        summary::process_summary(matches, &config, &preferences)?;
+        summary::process_summary(matches, config, preferences)?;
     } else if let Some(matches) = matches.subcommand_matches("configure") {
         preferences::process_configuration(matches)?;
     } else if let Some(matches) = matches.subcommand_matches("generate-shell-completions") {
@@ -45,19 +117,17 @@ fn main() -> Result<(), Error> {
 
         match shell {
             "bash" => {
-                generate::<Bash, _>(&mut cli::build_cli(&preferences), "fab", &mut io::stdout())
-            }
-            "zsh" => {
-                generate::<Zsh, _>(&mut cli::build_cli(&preferences), "fab", &mut io::stdout())
+                generate::<Bash, _>(&mut cli::build_cli(preferences), "fab", &mut io::stdout())
             }
+            "zsh" => generate::<Zsh, _>(&mut cli::build_cli(preferences), "fab", &mut io::stdout()),
             "fish" => {
-                generate::<Fish, _>(&mut cli::build_cli(&preferences), "fab", &mut io::stdout())
+                generate::<Fish, _>(&mut cli::build_cli(preferences), "fab", &mut io::stdout())
             }
             "elvish" => {
-                generate::<Elvish, _>(&mut cli::build_cli(&preferences), "fab", &mut io::stdout())
+                generate::<Elvish, _>(&mut cli::build_cli(preferences), "fab", &mut io::stdout())
             }
             "powershell" => generate::<PowerShell, _>(
-                &mut cli::build_cli(&preferences),
+                &mut cli::build_cli(preferences),
                 "fab",
                 &mut io::stdout(),
             ),
@@ -78,6 +148,8 @@ fn migrate_preferences(preferences: Preferences) -> Result<Preferences, Error> {
         default_sort: preferences.default_sort,
         default_task_priority: preferences.default_task_priority,
         summary_task_priority: preferences.summary_task_priority,
+        default_columns: preferences.default_columns,
+        saved_queries: preferences.saved_queries,
     };
     preferences::set_preferences(&preferences)?;
     Ok(preferences)
@@ -0,0 +1,417 @@
+use crate::errors::InvalidArgument;
+use crate::utils::format_timestamp;
+use crate::NO_BORDER_PRESET;
+use anyhow::{anyhow, Error};
+use clap::ArgMatches;
+use comfy_table::{Cell, ContentArrangement, Table};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The formats results can be printed in with `--format`.
+pub const FORMATS: [&str; 5] = ["table", "json", "csv", "tsv", "markdown"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    Markdown,
+}
+
+impl Format {
+    /// Reads the global `--format` argument. Without it, results are printed as a table.
+    pub fn from_matches(matches: &ArgMatches) -> Result<Format, Error> {
+        match matches.value_of("format").unwrap_or("table") {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "markdown" => Ok(Format::Markdown),
+            format => Err(InvalidArgument(format!(
+                "Unknown format {}. Must be one of {:?}",
+                format, FORMATS
+            ))
+            .into()),
+        }
+    }
+}
+
+/// How a listing is printed: its format, along with the fields chosen with
+/// `--columns` or the line given with `--template`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub format: Format,
+    columns: Option<Vec<String>>,
+    template: Option<String>,
+    /// With `--quiet`, nothing is printed and the exit code tells whether there
+    /// are any results.
+    pub quiet: bool,
+}
+
+impl Output {
+    /// Reads `--format`, `--columns` and `--template`. Without `--columns`, the
+    /// columns saved in the preferences for the command are used.
+    pub fn from_matches(
+        matches: &ArgMatches,
+        default_columns: Option<&Vec<String>>,
+    ) -> Result<Output, Error> {
+        let columns = match matches.value_of("columns") {
+            Some(columns) => Some(
+                columns
+                    .split(',')
+                    .map(|column| column.trim().to_string())
+                    .filter(|column| !column.is_empty())
+                    .collect(),
+            ),
+            None => default_columns
+                .filter(|columns| !columns.is_empty())
+                .cloned(),
+        };
+
+        Ok(Output {
+            format: Format::from_matches(matches)?,
+            columns,
+            template: matches.value_of("template").map(str::to_string),
+            quiet: matches.is_present("quiet"),
+        })
+    }
+
+    /// Whether the command should render its own, colored table.
+    pub fn is_default_table(&self) -> bool {
+        self.format == Format::Table && self.columns.is_none() && self.template.is_none()
+    }
+
+    pub fn print<R: Record>(&self, records: &[R]) -> Result<(), Error> {
+        if let Some(template) = &self.template {
+            for record in records {
+                println!("{}", render_template(template, record)?);
+            }
+            return Ok(());
+        }
+
+        let fields = match &self.columns {
+            Some(columns) => resolve_fields::<R>(columns)?,
+            None => R::FIELDS.to_vec(),
+        };
+
+        match self.format {
+            Format::Table => println!("{}", format_table(records, &fields)?),
+            Format::Json if self.columns.is_none() => print_records(self.format, records)?,
+            Format::Json => {
+                let mut values = vec![];
+                for record in records {
+                    let value = serde_json::to_value(record)?;
+                    let selected: Map<String, Value> = fields
+                        .iter()
+                        .map(|field| (field.to_string(), value[*field].clone()))
+                        .collect();
+                    values.push(Value::Object(selected));
+                }
+                println!("{}", serde_json::to_string_pretty(&values)?);
+            }
+            _ => println!("{}", format_rows(self.format, records, &fields)?),
+        }
+        Ok(())
+    }
+}
+
+/// Maps the columns given by the user to fields of the record. Besides the field
+/// names, `created` and `updated` can be used for the dates.
+fn resolve_fields<R: Record>(columns: &[String]) -> Result<Vec<&'static str>, Error> {
+    columns
+        .iter()
+        .map(|column| {
+            let name = match column.as_str() {
+                "created" => "date_created",
+                "updated" => "date_modified",
+                name => name,
+            };
+            R::FIELDS
+                .iter()
+                .find(|&&field| field == name)
+                .copied()
+                .ok_or_else(|| {
+                    InvalidArgument(format!(
+                        "Unknown column {}. Must be one of {}",
+                        column,
+                        R::FIELDS.join(", ")
+                    ))
+                    .into()
+                })
+        })
+        .collect()
+}
+
+/// Fills in the `{field}` placeholders of a `--template` with the fields of the
+/// record. `\t` and `\n` are replaced with a tab and a newline.
+fn render_template<R: Record>(template: &str, record: &R) -> Result<String, Error> {
+    let value = serde_json::to_value(record)?;
+    let template = template.replace("\\t", "\t").replace("\\n", "\n");
+
+    let mut line = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| InvalidArgument(format!("Unclosed {{ in template {}", template)))?;
+
+        let column = rest[start + 1..end].to_string();
+        let field = resolve_fields::<R>(&[column])?[0];
+
+        line.push_str(&rest[..start]);
+        line.push_str(&display_text(field, &value[field]));
+        rest = &rest[end + 1..];
+    }
+    line.push_str(rest);
+
+    Ok(line)
+}
+
+fn format_table<R: Record>(records: &[R], fields: &[&str]) -> Result<Table, Error> {
+    let mut table = Table::new();
+
+    table
+        .load_preset(NO_BORDER_PRESET)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for record in records {
+        let value = serde_json::to_value(record)?;
+        table.add_row(
+            fields
+                .iter()
+                .map(|field| record.cell(field, display_text(field, &value[*field])))
+                .collect::<Vec<Cell>>(),
+        );
+    }
+
+    Ok(table)
+}
+
+/// An object that can be printed in the machine-readable formats. The JSON schema
+/// of a record is its serialized form and `FIELDS` are the columns of the csv, tsv
+/// and markdown output, in order. Both are meant to be stable for scripts: fields
+/// can be added, but not renamed or removed.
+pub trait Record: Serialize {
+    const FIELDS: &'static [&'static str];
+
+    /// The cell of a field in a table made with `--columns`. Records can override
+    /// this to color fields like they are in the default table.
+    fn cell(&self, _field: &str, text: String) -> Cell {
+        Cell::new(text)
+    }
+}
+
+/// Prints the records in any format but `Format::Table`, which every command
+/// renders itself.
+pub fn print_records<R: Record>(format: Format, records: &[R]) -> Result<(), Error> {
+    println!("{}", format_records(format, records)?);
+    Ok(())
+}
+
+/// Prints a single record, e.g. of `fab task T123`. In JSON this is an object
+/// rather than a list.
+pub fn print_record<R: Record>(format: Format, record: &R) -> Result<(), Error> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(record)?),
+        _ => print_records(format, std::slice::from_ref(record))?,
+    }
+    Ok(())
+}
+
+pub fn format_records<R: Record>(format: Format, records: &[R]) -> Result<String, Error> {
+    if format == Format::Json {
+        return Ok(serde_json::to_string_pretty(records)?);
+    }
+
+    format_rows(format, records, R::FIELDS)
+}
+
+/// Formats the given fields of the records as csv, tsv or a markdown table.
+fn format_rows<R: Record>(format: Format, records: &[R], fields: &[&str]) -> Result<String, Error> {
+    let mut rows = vec![fields.iter().map(|field| field.to_string()).collect()];
+    for record in records {
+        let value = serde_json::to_value(record)?;
+        rows.push(
+            fields
+                .iter()
+                .map(|field| to_text(&value[*field]))
+                .collect::<Vec<String>>(),
+        );
+    }
+
+    let lines: Vec<String> = match format {
+        Format::Csv => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| escape_csv(cell))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect(),
+        Format::Tsv => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| escape_tsv(cell))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect(),
+        Format::Markdown => {
+            let mut lines: Vec<String> = rows.iter().map(|row| markdown_row(row)).collect();
+            let separator = vec!["---".to_string(); fields.len()];
+            lines.insert(1, markdown_row(&separator));
+            lines
+        }
+        Format::Table | Format::Json => {
+            return Err(anyhow!("{:?} output can't be printed as rows", format))
+        }
+    };
+
+    Ok(lines.join("\n"))
+}
+
+/// The text of a field in a row. Lists, like the log urls of a build, are joined
+/// by spaces.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(to_text).collect::<Vec<_>>().join(" "),
+        value => value.to_string(),
+    }
+}
+
+/// The text of a field in a table or template, which are read by people rather
+/// than scripts. Dates, the fields named `date_*`, are formatted instead of being
+/// printed as unix timestamps.
+fn display_text(field: &str, value: &Value) -> String {
+    match value.as_i64() {
+        Some(timestamp) if field.starts_with("date_") => format_timestamp(timestamp),
+        _ => to_text(value),
+    }
+}
+
+fn escape_csv(cell: &str) -> String {
+    if cell.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+fn escape_tsv(cell: &str) -> String {
+    cell.replace(&['\t', '\n', '\r'][..], " ")
+}
+
+fn markdown_row(row: &[String]) -> String {
+    let cells: Vec<String> = row
+        .iter()
+        .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct TestRecord {
+        id: i32,
+        title: String,
+        owner: Option<String>,
+    }
+
+    impl Record for TestRecord {
+        const FIELDS: &'static [&'static str] = &["id", "title", "owner"];
+    }
+
+    fn records() -> Vec<TestRecord> {
+        vec![
+            TestRecord {
+                id: 1,
+                title: "Crash, on \"startup\"".to_string(),
+                owner: Some("alice".to_string()),
+            },
+            TestRecord {
+                id: 2,
+                title: "Fix | pipes".to_string(),
+                owner: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            format_records(Format::Csv, &records()).unwrap(),
+            "id,title,owner\n1,\"Crash, on \"\"startup\"\"\",alice\n2,Fix | pipes,"
+        );
+    }
+
+    #[test]
+    fn test_tsv() {
+        assert_eq!(
+            format_records(Format::Tsv, &records()).unwrap(),
+            "id\ttitle\towner\n1\tCrash, on \"startup\"\talice\n2\tFix | pipes\t"
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(
+            format_records(Format::Markdown, &records()).unwrap(),
+            "| id | title | owner |\n| --- | --- | --- |\n| 1 | Crash, on \"startup\" | alice |\n| 2 | Fix \\| pipes |  |"
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let record = &records()[0];
+        assert_eq!(
+            render_template("{id}\\t{title} ({owner})", record).unwrap(),
+            "1\tCrash, on \"startup\" (alice)"
+        );
+        assert!(render_template("{id} {nope}", record).is_err());
+        assert!(render_template("{id", record).is_err());
+    }
+
+    #[test]
+    fn test_columns() {
+        let records = records();
+        let output = Output {
+            format: Format::Csv,
+            columns: Some(vec!["owner".to_string(), "id".to_string()]),
+            template: None,
+            quiet: false,
+        };
+        let fields = resolve_fields::<TestRecord>(output.columns.as_ref().unwrap()).unwrap();
+
+        assert_eq!(
+            format_rows(output.format, &records, &fields).unwrap(),
+            "owner,id\nalice,1\n,2"
+        );
+        assert!(resolve_fields::<TestRecord>(&["updated".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_display_text() {
+        assert_eq!(
+            "2020-04-12 16:26 UTC",
+            display_text("date_modified", &json!(1_586_708_760))
+        );
+        assert_eq!("1586708760", display_text("id", &json!(1_586_708_760)));
+        assert_eq!("", display_text("date_created", &Value::Null));
+    }
+
+    #[test]
+    fn test_json_keeps_field_order() {
+        let json = format_records(Format::Json, &records()).unwrap();
+        assert!(json.find("\"id\"").unwrap() < json.find("\"title\"").unwrap());
+        assert!(json.contains("\"owner\": null"));
+    }
+}
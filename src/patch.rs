@@ -0,0 +1,129 @@
+use crate::diffs::{get_diff, get_revision, get_revision_diff, get_revision_record};
+use crate::errors::InvalidArgument;
+use crate::git;
+use crate::output::{self, Format};
+use crate::structs::FabConfig;
+use crate::utils::parse_object_id;
+use anyhow::{anyhow, Error};
+use clap::ArgMatches;
+use fab::conduit::Params;
+use tokio::runtime::Runtime;
+
+const GET_RAW_DIFF: &str = "differential.getrawdiff";
+
+/// Get the raw patch of the diff with the given id.
+pub async fn get_raw_diff(config: &FabConfig, diff_id: i32) -> Result<String, Error> {
+    let params = Params::new().set("diffID", diff_id);
+
+    config.client().call::<String>(GET_RAW_DIFF, &params).await
+}
+
+/// Applies the patch of a revision to a new branch, or prints it with `--print`.
+pub fn process_patch_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = parse_object_id('D', matches.value_of("id").unwrap())?;
+    let format = Format::from_matches(matches)?;
+
+    let mut runtime = Runtime::new()?;
+
+    let revision = runtime.block_on(get_revision(config, id))?;
+
+    let diff_id = match matches.value_of("diff") {
+        Some(diff_id) => {
+            let diff_id = diff_id
+                .parse::<i32>()
+                .map_err(|_| InvalidArgument(format!("'{}' is not a valid diff id", diff_id)))?;
+
+            runtime
+                .block_on(get_revision_diff(config, &revision.phid, diff_id))?
+                .ok_or_else(|| {
+                    InvalidArgument(format!("Diff {} isn't a diff of D{}", diff_id, id))
+                })?
+                .id
+        }
+        None => {
+            runtime
+                .block_on(get_diff(config, &revision.fields.diff_phid))?
+                .ok_or_else(|| anyhow!("D{} doesn't have any diffs", id))?
+                .id
+        }
+    };
+
+    let patch = runtime.block_on(get_raw_diff(config, diff_id))?;
+
+    if matches.is_present("print") {
+        print!("{}", patch);
+        return Ok(());
+    }
+
+    // Make sure the patch applies before leaving the current branch.
+    git::check_apply(&patch)?;
+
+    let branch = get_branch_name(id, git::branch_exists);
+
+    git::checkout_new_branch(&branch)?;
+    git::apply(&patch)?;
+    git::commit(&get_commit_message(
+        &revision.fields.title,
+        &revision.fields.summary,
+        &revision.url(config),
+    ))?;
+
+    if format != Format::Table {
+        return output::print_record(format, &runtime.block_on(get_revision_record(config, id))?);
+    }
+
+    println!("Applied diff {} of D{} on branch {}", diff_id, id, branch);
+    Ok(())
+}
+
+/// Names the branch after the revision, adding a suffix when the branch already exists.
+fn get_branch_name(id: i32, exists: impl Fn(&str) -> bool) -> String {
+    let branch = format!("D{}", id);
+    if !exists(&branch) {
+        return branch;
+    }
+
+    (1..)
+        .map(|suffix| format!("{}_{}", branch, suffix))
+        .find(|candidate| !exists(candidate))
+        .unwrap()
+}
+
+/// The message of the commit the patch is applied in. The summary is left out when
+/// the revision doesn't have one.
+fn get_commit_message(title: &str, summary: &str, url: &str) -> String {
+    let summary = summary.trim();
+    if summary.is_empty() {
+        format!("{}\n\nDifferential Revision: {}", title, url)
+    } else {
+        format!("{}\n\n{}\n\nDifferential Revision: {}", title, summary, url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_branch_name() {
+        assert_eq!("D12", get_branch_name(12, |_| false));
+        assert_eq!("D12_1", get_branch_name(12, |branch| branch == "D12"));
+        assert_eq!(
+            "D12_2",
+            get_branch_name(12, |branch| branch == "D12" || branch == "D12_1")
+        );
+    }
+
+    #[test]
+    fn test_get_commit_message() {
+        let url = "https://phab.example.com/D12";
+        assert_eq!(
+            "Fix crash\n\nIt crashed.\n\nDifferential Revision: https://phab.example.com/D12",
+            get_commit_message("Fix crash", "It crashed.", url)
+        );
+        assert_eq!(
+            "Fix crash\n\nDifferential Revision: https://phab.example.com/D12",
+            get_commit_message("Fix crash", "", url)
+        );
+    }
+}
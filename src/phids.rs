@@ -0,0 +1,47 @@
+use crate::structs::FabConfig;
+use anyhow::Error;
+use fab::conduit::Params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const PHID_QUERY: &str = "phid.query";
+
+/// Resolves the given PHIDs into handles so that users, repositories and
+/// other objects can be shown by their name instead of their PHID.
+pub async fn get_handles(
+    phids: &[String],
+    config: &FabConfig,
+) -> Result<HashMap<String, Handle>, Error> {
+    // Conduit returns an empty list instead of an empty map when nothing is queried.
+    if phids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let params = Params::new().list("phids", phids);
+
+    config
+        .client()
+        .call::<HashMap<String, Handle>>(PHID_QUERY, &params)
+        .await
+}
+
+/// Returns the display name for the PHID, falling back to the PHID itself when
+/// it couldn't be resolved.
+pub fn get_name(handles: &HashMap<String, Handle>, phid: &str) -> String {
+    handles
+        .get(phid)
+        .map(|handle| handle.name.clone())
+        .unwrap_or_else(|| phid.to_string())
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Handle {
+    pub phid: String,
+    pub uri: String,
+    pub name: String,
+    #[serde(rename = "fullName")]
+    pub full_name: String,
+    #[serde(rename = "typeName")]
+    pub type_name: String,
+    pub status: String,
+}
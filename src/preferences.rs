@@ -1,11 +1,16 @@
+use crate::cli::TASK_PRIORITIES;
 use anyhow::Error;
 use clap::ArgMatches;
 use console::style;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Checkboxes, Input, Select};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io;
 
+/// Commands whose default `--columns` can be configured.
+const COLUMN_COMMANDS: [&str; 3] = ["tasks", "diffs", "search"];
+
 /// Get user's preferences
 pub fn get_preferences() -> Result<Preferences, Error> {
     let prefs = confy::load::<Preferences>("fab")?;
@@ -18,7 +23,7 @@ pub fn set_preferences(preferences: &Preferences) -> Result<(), Error> {
     Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Preferences {
     pub summary_task_priority: Vec<String>,
     pub default_task_priority: Vec<String>,
@@ -26,6 +31,12 @@ pub struct Preferences {
     pub default_sort: String,
     #[serde(default = "default_limit")]
     pub default_limit_str: String,
+    /// The `--columns` used by default, keyed by the command, e.g. `tasks`.
+    #[serde(default)]
+    pub default_columns: HashMap<String, Vec<String>>,
+    /// Arguments of the queries saved with `fab query save`, keyed by their name.
+    #[serde(default)]
+    pub saved_queries: HashMap<String, Vec<String>>,
 }
 
 fn default_limit() -> String {
@@ -40,6 +51,8 @@ impl ::std::default::Default for Preferences {
             default_limit: 20,
             default_limit_str: "20".to_string(),
             default_sort: "updated".to_string(),
+            default_columns: HashMap::new(),
+            saved_queries: HashMap::new(),
         }
     }
 }
@@ -52,14 +65,7 @@ pub fn process_configuration(matches: &ArgMatches) -> Result<(), Error> {
     }
     let current_preferences = get_preferences()?;
 
-    let possible_priorities = vec![
-        "unbreak-now",
-        "needs-triage",
-        "high",
-        "normal",
-        "low",
-        "wishlist",
-    ];
+    let possible_priorities = TASK_PRIORITIES;
 
     println!(
         "{}",
@@ -113,24 +119,66 @@ pub fn process_configuration(matches: &ArgMatches) -> Result<(), Error> {
 
     let default_sort = sort_values[default_sort];
 
+    let mut default_columns = HashMap::new();
+    for command in COLUMN_COMMANDS.iter() {
+        println!(
+            "{}",
+            style(format!(
+                "Choose the default columns of `fab {}`, separated by commas",
+                command
+            ))
+            .bold()
+            .underlined()
+        );
+        println!("(Leave empty to keep the default table)");
+
+        let current_columns = current_preferences
+            .default_columns
+            .get(*command)
+            .map(|columns| columns.join(","))
+            .unwrap_or_default();
+        let columns: String = Input::with_theme(&ColorfulTheme::default())
+            .with_initial_text(&current_columns)
+            .allow_empty(true)
+            .interact()?;
+
+        let columns: Vec<String> = columns
+            .split(',')
+            .map(|column| column.trim().to_string())
+            .filter(|column| !column.is_empty())
+            .collect();
+        if !columns.is_empty() {
+            default_columns.insert(command.to_string(), columns);
+        }
+    }
+
     let new_preferences = Preferences {
         summary_task_priority: summary_priorities,
         default_task_priority: default_task_priorities,
         default_limit,
         default_limit_str: format!("{}", default_limit),
         default_sort: default_sort.to_string(),
+        default_columns,
+        saved_queries: current_preferences.saved_queries,
     };
 
     set_preferences(&new_preferences)
 }
 
 fn reset_preferences() -> Result<(), Error> {
+    // Saved queries aren't preferences, so they survive a reset.
+    let saved_queries = get_preferences()
+        .map(|preferences| preferences.saved_queries)
+        .unwrap_or_default();
+
     let default_preferences = Preferences {
         default_limit: 20,
         default_limit_str: "20".to_string(),
         default_task_priority: vec![String::from("high")],
         summary_task_priority: vec![String::from("high")],
         default_sort: "updated".to_string(),
+        default_columns: HashMap::new(),
+        saved_queries,
     };
 
     set_preferences(&default_preferences)
@@ -0,0 +1,85 @@
+use crate::auth::{prompt_profile, read_config, write_config, Profiles};
+use crate::errors::{InvalidArgument, NotFound};
+use crate::NO_BORDER_PRESET;
+use anyhow::Error;
+use clap::ArgMatches;
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+
+/// Adds, lists, removes or picks the default profile, e.g. `fab profile add oss`.
+pub fn process_profile_command(matches: &ArgMatches) -> Result<(), Error> {
+    // Profiles can be added before fab has been set up.
+    let mut config = read_config()?.unwrap_or_default();
+
+    if let Some(matches) = matches.subcommand_matches("add") {
+        let name = matches.value_of("name").unwrap();
+        if config.profiles.contains_key(name) {
+            return Err(InvalidArgument(format!(
+                "There already is a profile named {}. Remove it first with `fab profile remove {}`",
+                name, name
+            ))
+            .into());
+        }
+
+        config.add(name, prompt_profile()?);
+        write_config(&config)?;
+
+        println!(
+            "Added profile {}. Use it with `fab --profile {}`",
+            name, name
+        );
+    } else if matches.subcommand_matches("list").is_some() {
+        let mut table = Table::new();
+
+        table
+            .load_preset(NO_BORDER_PRESET)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+
+        for (name, profile) in &config.profiles {
+            let default = if config.default_profile.as_ref() == Some(name) {
+                "default"
+            } else {
+                ""
+            };
+            table.add_row(vec![
+                Cell::new(name).add_attribute(Attribute::Bold),
+                Cell::new(&profile.hosted_instance),
+                Cell::new(default),
+            ]);
+        }
+
+        println!("{}", table);
+    } else if let Some(matches) = matches.subcommand_matches("remove") {
+        let name = matches.value_of("name").unwrap();
+        ensure_profile_exists(&config, name)?;
+
+        if config.default_profile.as_deref() == Some(name) {
+            return Err(InvalidArgument(format!(
+                "{} is the default profile. Pick another one with `fab profile use <name>` first",
+                name
+            ))
+            .into());
+        }
+
+        config.profiles.remove(name);
+        write_config(&config)?;
+
+        println!("Removed profile {}", name);
+    } else if let Some(matches) = matches.subcommand_matches("use") {
+        let name = matches.value_of("name").unwrap();
+        ensure_profile_exists(&config, name)?;
+
+        config.default_profile = Some(name.to_string());
+        write_config(&config)?;
+
+        println!("Using profile {} by default", name);
+    }
+    Ok(())
+}
+
+fn ensure_profile_exists(config: &Profiles, name: &str) -> Result<(), Error> {
+    if config.profiles.contains_key(name) {
+        Ok(())
+    } else {
+        Err(NotFound(format!("There is no profile named {}", name)).into())
+    }
+}
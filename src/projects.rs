@@ -0,0 +1,133 @@
+use crate::errors::NotFound;
+use crate::structs::FabConfig;
+use anyhow::Error;
+use fab::conduit::{Limit, Search};
+use serde::Deserialize;
+
+const PROJECT_SEARCH: &str = "project.search";
+const COLUMN_SEARCH: &str = "project.column.search";
+
+/// Find a project by its name, ignoring case. Projects that merely contain the name
+/// are only suggested, so that a typo never picks the wrong project.
+pub async fn get_project(name: &str, config: &FabConfig) -> Result<Project, Error> {
+    let search = Search::new(PROJECT_SEARCH).constraint("name", name);
+
+    let projects = config.client().search(&search, Limit::All).await?;
+
+    Ok(find_exact_match(projects, name)?)
+}
+
+/// Get the columns of the project's workboard, in the order they appear on the board.
+pub async fn get_columns(project: &Project, config: &FabConfig) -> Result<Vec<Column>, Error> {
+    let search = Search::new(COLUMN_SEARCH).constraints("projects", &[project.phid.as_str()]);
+
+    let mut columns: Vec<Column> = config.client().search(&search, Limit::All).await?;
+
+    columns.sort_by_key(|column| column.fields.sequence);
+
+    Ok(columns)
+}
+
+/// Find a column of the project's workboard by its name.
+pub async fn get_column(
+    project: &Project,
+    name: &str,
+    config: &FabConfig,
+) -> Result<Column, Error> {
+    get_columns(project, config)
+        .await?
+        .into_iter()
+        .find(|column| column.fields.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| {
+            NotFound(format!(
+                "Could not find column {} on the workboard of {}",
+                name, project.fields.name
+            ))
+            .into()
+        })
+}
+
+fn find_exact_match(projects: Vec<Project>, name: &str) -> Result<Project, NotFound> {
+    let name = name.trim();
+    let candidates: Vec<String> = projects
+        .iter()
+        .map(|project| project.fields.name.clone())
+        .collect();
+
+    projects
+        .into_iter()
+        .find(|project| project.fields.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            if candidates.is_empty() {
+                NotFound(format!("Could not find project {}", name))
+            } else {
+                NotFound(format!(
+                    "Could not find project {}. Did you mean one of: {}?",
+                    name,
+                    candidates.join(", ")
+                ))
+            }
+        })
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Project {
+    pub id: i32,
+    pub phid: String,
+    pub fields: ProjectFields,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProjectFields {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Column {
+    pub id: i32,
+    pub phid: String,
+    pub fields: ColumnFields,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ColumnFields {
+    pub name: String,
+    #[serde(default)]
+    pub sequence: i32,
+    #[serde(default, rename = "isHidden")]
+    pub is_hidden: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(id: i32, name: &str) -> Project {
+        Project {
+            id,
+            phid: format!("PHID-PROJ-{}", id),
+            fields: ProjectFields {
+                name: name.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_find_exact_match() {
+        let projects = vec![project(1, "Android Sprint"), project(2, "Android")];
+        assert_eq!(2, find_exact_match(projects, "android").unwrap().id);
+
+        let projects = vec![project(1, "Android Sprint"), project(2, "Android Infra")];
+        assert_eq!(
+            "Could not find project Android. Did you mean one of: Android Sprint, Android Infra?",
+            find_exact_match(projects, "Android")
+                .unwrap_err()
+                .to_string()
+        );
+
+        assert_eq!(
+            "Could not find project Android",
+            find_exact_match(vec![], "Android").unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::cli;
+use crate::errors::{InvalidArgument, NotFound};
+use crate::preferences::{set_preferences, Preferences};
+use crate::NO_BORDER_PRESET;
+use anyhow::Error;
+use clap::{App, Arg, ArgMatches, ArgSettings};
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+
+/// Commands that can't be saved as a query, so that queries can't run each other.
+const UNSAVABLE_COMMANDS: [&str; 2] = ["q", "query"];
+
+/// Saves, lists or removes saved queries, e.g. `fab query save standup tasks --priority high`.
+pub fn process_query_command(matches: &ArgMatches, preferences: &Preferences) -> Result<(), Error> {
+    if let Some(matches) = matches.subcommand_matches("save") {
+        let name = matches.value_of("name").unwrap();
+        let args: Vec<String> = matches
+            .values_of("args")
+            .unwrap()
+            .map(str::to_string)
+            .collect();
+
+        validate_query(&args, preferences)?;
+
+        let mut preferences = preferences.clone();
+        preferences.saved_queries.insert(name.to_string(), args);
+        set_preferences(&preferences)?;
+
+        println!("Saved query {}. Run it with `fab q {}`", name, name);
+    } else if matches.subcommand_matches("list").is_some() {
+        let mut names: Vec<&String> = preferences.saved_queries.keys().collect();
+        names.sort();
+
+        let mut table = Table::new();
+
+        table
+            .load_preset(NO_BORDER_PRESET)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+
+        for name in names {
+            table.add_row(vec![
+                Cell::new(name).add_attribute(Attribute::Bold),
+                Cell::new(format!("fab {}", preferences.saved_queries[name].join(" "))),
+            ]);
+        }
+
+        println!("{}", table);
+    } else if let Some(matches) = matches.subcommand_matches("remove") {
+        let name = matches.value_of("name").unwrap();
+
+        let mut preferences = preferences.clone();
+        preferences
+            .saved_queries
+            .remove(name)
+            .ok_or_else(|| NotFound(format!("There is no saved query named {}", name)))?;
+        set_preferences(&preferences)?;
+
+        println!("Removed query {}", name);
+    }
+    Ok(())
+}
+
+/// The command line of a saved query for `fab q <name>`, along with the global
+/// options given before `q` and any extra arguments that were given after its name.
+pub fn get_query_args(
+    matches: &ArgMatches,
+    preferences: &Preferences,
+) -> Result<Vec<String>, Error> {
+    let name = matches.value_of("name").unwrap();
+    let saved_args = preferences.saved_queries.get(name).ok_or_else(|| {
+        NotFound(format!(
+            "There is no saved query named {}. Save one with `fab query save {} <command>`",
+            name, name
+        ))
+    })?;
+
+    // `--profile` and `--non-interactive` have been used to set fab up already.
+    let global_args: Vec<String> = match matches.value_of("format") {
+        Some(format) => vec!["--format".to_string(), format.to_string()],
+        None => vec![],
+    };
+    let extra_args: Vec<String> = matches
+        .values_of("args")
+        .map(|args| args.map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let app = cli::build_cli(preferences);
+    let args = merge_args(&app, saved_args, &global_args);
+
+    let mut command_line = vec!["fab".to_string()];
+    command_line.extend(merge_args(&app, &args, &extra_args));
+    Ok(command_line)
+}
+
+/// Adds the extra arguments to the saved ones. An option that is given again replaces
+/// the saved one along with its values, so `fab q standup -l 5` works even when
+/// `standup` was saved with `--limit 10`.
+fn merge_args(app: &App, saved_args: &[String], extra_args: &[String]) -> Vec<String> {
+    let command = saved_args.first().and_then(|name| {
+        app.get_subcommands()
+            .find(|command| command.get_name() == name)
+    });
+    let options: Vec<&Arg> = app
+        .get_arguments()
+        .chain(command.into_iter().flat_map(App::get_arguments))
+        .collect();
+
+    let overridden: Vec<&str> = extra_args
+        .iter()
+        .filter_map(|arg| find_option(&options, arg))
+        .map(Arg::get_name)
+        .collect();
+
+    let mut args = vec![];
+    let mut saved_args = saved_args.iter().peekable();
+    while let Some(arg) = saved_args.next() {
+        let option = match find_option(&options, arg) {
+            Some(option) if overridden.contains(&option.get_name()) => option,
+            _ => {
+                args.push(arg.clone());
+                continue;
+            }
+        };
+
+        // Values given as `--limit=10` or `-l10` are part of the option itself.
+        let has_value = arg.contains('=') || (!arg.starts_with("--") && arg.len() > 2);
+        if option.is_set(ArgSettings::TakesValue) && !has_value {
+            saved_args.next();
+            if option.is_set(ArgSettings::MultipleValues) {
+                while saved_args
+                    .peek()
+                    .map_or(false, |value| !value.starts_with('-'))
+                {
+                    saved_args.next();
+                }
+            }
+        }
+    }
+    args.extend(extra_args.iter().cloned());
+    args
+}
+
+/// The option an argument like `--limit`, `--limit=10` or `-l` stands for.
+fn find_option<'a, 'help>(options: &[&'a Arg<'help>], arg: &str) -> Option<&'a Arg<'help>> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let long = long.split('=').next().unwrap_or(long);
+        options
+            .iter()
+            .copied()
+            .find(|option| option.get_long() == Some(long))
+    } else if let Some(short) = arg.strip_prefix('-') {
+        let short = short.chars().next()?;
+        options
+            .iter()
+            .copied()
+            .find(|option| option.get_short() == Some(short))
+    } else {
+        None
+    }
+}
+
+/// Makes sure a query is a valid fab command before it is saved.
+fn validate_query(args: &[String], preferences: &Preferences) -> Result<(), Error> {
+    if UNSAVABLE_COMMANDS.contains(&args[0].as_str()) {
+        return Err(InvalidArgument(format!("`fab {}` can't be saved as a query", args[0])).into());
+    }
+
+    let mut command_line = vec!["fab".to_string()];
+    command_line.extend(args.iter().cloned());
+
+    cli::build_cli(preferences).try_get_matches_from(command_line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn preferences() -> Preferences {
+        let mut preferences = Preferences::default();
+        preferences.saved_queries.insert(
+            "standup".to_string(),
+            strings(&["tasks", "--priority", "high", "normal", "--limit", "10"]),
+        );
+        preferences.saved_queries.insert(
+            "comments".to_string(),
+            strings(&["diff", "--comments", "123"]),
+        );
+        preferences
+    }
+
+    #[test]
+    fn test_get_query_args() {
+        let preferences = preferences();
+        let query_args = |command_line: &[&str]| {
+            let matches = cli::build_cli(&preferences)
+                .try_get_matches_from(command_line)
+                .unwrap();
+            get_query_args(matches.subcommand_matches("q").unwrap(), &preferences)
+                .map(|args| args.join(" "))
+        };
+
+        assert_eq!(
+            "fab tasks --priority high normal --limit 10",
+            query_args(&["fab", "q", "standup"]).unwrap()
+        );
+        assert_eq!(
+            "fab tasks --priority high normal --limit 5",
+            query_args(&["fab", "q", "standup", "--limit", "5"]).unwrap()
+        );
+        assert_eq!(
+            "fab tasks --priority high normal -l 5",
+            query_args(&["fab", "q", "standup", "-l", "5"]).unwrap()
+        );
+        assert_eq!(
+            "fab tasks --limit 10 --priority=low",
+            query_args(&["fab", "q", "standup", "--priority=low"]).unwrap()
+        );
+        assert_eq!(
+            "fab diff 123 --comments",
+            query_args(&["fab", "q", "comments", "--comments"]).unwrap()
+        );
+        assert_eq!(
+            "fab tasks --priority high normal --limit 10 --format json",
+            query_args(&["fab", "--format", "json", "q", "standup"]).unwrap()
+        );
+        assert_eq!(
+            "fab tasks --priority high normal --limit 10 --format csv",
+            query_args(&["fab", "--format", "json", "q", "standup", "--format", "csv"]).unwrap()
+        );
+        assert!(query_args(&["fab", "q", "retro"]).is_err());
+    }
+
+    #[test]
+    fn test_query_args_parse() {
+        let preferences = preferences();
+        let matches = cli::build_cli(&preferences)
+            .try_get_matches_from(&["fab", "q", "standup", "-l", "5"])
+            .unwrap();
+        let args = get_query_args(matches.subcommand_matches("q").unwrap(), &preferences).unwrap();
+
+        let matches = cli::build_cli(&preferences)
+            .try_get_matches_from(args)
+            .unwrap();
+        let tasks = matches.subcommand_matches("tasks").unwrap();
+        assert_eq!(Some("5"), tasks.value_of("limit"));
+    }
+
+    #[test]
+    fn test_validate_query() {
+        let preferences = Preferences::default();
+
+        assert!(validate_query(&strings(&["tasks", "--priority", "high"]), &preferences).is_ok());
+        assert!(validate_query(&strings(&["q", "standup"]), &preferences).is_err());
+        assert!(validate_query(&strings(&["query", "list"]), &preferences).is_err());
+        assert!(validate_query(&strings(&["tasks", "--no-such-option"]), &preferences).is_err());
+        assert!(validate_query(&strings(&["no-such-command"]), &preferences).is_err());
+    }
+}
@@ -0,0 +1,476 @@
+use console::{strip_ansi_codes, style};
+
+/// Inline formatting of Remarkup and the delimiters that surround it.
+#[derive(Clone, Copy)]
+enum Inline {
+    Bold,
+    Italic,
+    Monospace,
+    Deleted,
+    Underlined,
+}
+
+const DELIMITERS: [(&str, Inline); 6] = [
+    ("**", Inline::Bold),
+    ("//", Inline::Italic),
+    ("##", Inline::Monospace),
+    ("`", Inline::Monospace),
+    ("~~", Inline::Deleted),
+    ("__", Inline::Underlined),
+];
+
+/// Renders a Remarkup document, like the description of a task or the summary of a
+/// revision, as styled text for the terminal.
+pub fn render(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut output: Vec<String> = vec![];
+    // Counters of the numbered lists, one per level of nesting.
+    let mut counters: Vec<usize> = vec![];
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") {
+            // Fenced code block, which may also be closed on its opening line.
+            let opening = trimmed.trim_start_matches('`');
+            if opening.ends_with("```") {
+                output.push(render_code(opening.trim_end_matches('`')));
+                i += 1;
+                continue;
+            }
+
+            let mut code = vec![];
+            if !opening.is_empty() && !opening.starts_with("lang=") {
+                code.push(opening);
+            }
+            i += 1;
+            while i < lines.len() && !lines[i].trim_end().ends_with("```") {
+                code.push(lines[i]);
+                i += 1;
+            }
+            if i < lines.len() {
+                let closing = lines[i].trim_end().trim_end_matches('`');
+                if !closing.trim().is_empty() {
+                    code.push(closing);
+                }
+            }
+            output.extend(code.into_iter().map(render_code));
+            counters.clear();
+            i += 1;
+            continue;
+        }
+
+        if is_table_row(trimmed) {
+            let mut rows = vec![];
+            while i < lines.len() && is_table_row(lines[i].trim()) {
+                rows.push(lines[i].trim());
+                i += 1;
+            }
+            output.extend(render_table(&rows));
+            counters.clear();
+            continue;
+        }
+
+        if let Some((depth, ordered, content)) = parse_list_item(line) {
+            counters.truncate(depth + 1);
+            counters.resize(depth + 1, 0);
+            counters[depth] += 1;
+
+            let bullet = if ordered {
+                format!("{}.", counters[depth])
+            } else {
+                String::from("•")
+            };
+            output.push(format!(
+                "{}{} {}",
+                "  ".repeat(depth + 1),
+                bullet,
+                render_inline(content)
+            ));
+            i += 1;
+            continue;
+        }
+
+        counters.clear();
+
+        if line.starts_with("  ") && !trimmed.is_empty() {
+            // Lines indented by two spaces are code blocks.
+            while i < lines.len() && lines[i].starts_with("  ") {
+                output.push(render_code(&lines[i][2..]));
+                i += 1;
+            }
+            continue;
+        }
+
+        if let Some(header) = parse_header(trimmed) {
+            output.push(style(render_inline(header)).bold().underlined().to_string());
+        } else if trimmed.starts_with('>') {
+            let quote = trimmed.trim_start_matches('>').trim_start();
+            output.push(format!(
+                "{} {}",
+                style("│").dim(),
+                style(render_inline(quote)).dim()
+            ));
+        } else {
+            output.push(render_inline(line));
+        }
+        i += 1;
+    }
+
+    output.join("\n")
+}
+
+fn render_code(line: &str) -> String {
+    format!("    {}", style(line).cyan())
+}
+
+/// Parses headers like `= Large Header =` or `== Smaller Header`.
+fn parse_header(line: &str) -> Option<&str> {
+    if !line.starts_with('=') {
+        return None;
+    }
+
+    let header = line.trim_matches('=').trim();
+    if header.is_empty() {
+        None
+    } else {
+        Some(header)
+    }
+}
+
+/// Parses list items like `- item`, `* item` or `# numbered item`. Nesting is given
+/// either by repeating the marker (`-- nested`) or by indenting the item.
+///
+/// Returns the depth of the item, whether the list is numbered and the content.
+fn parse_list_item(line: &str) -> Option<(usize, bool, &str)> {
+    let content = line.trim_start();
+    let indent = line.len() - content.len();
+
+    let marker = content.chars().next()?;
+    if marker != '-' && marker != '*' && marker != '#' {
+        return None;
+    }
+
+    let markers = content.chars().take_while(|&c| c == marker).count();
+    let rest = &content[markers..];
+    if !rest.starts_with(' ') || rest.trim().is_empty() {
+        return None;
+    }
+
+    Some((markers - 1 + indent / 2, marker == '#', rest.trim()))
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.len() > 1 && line.starts_with('|') && line.ends_with('|')
+}
+
+fn is_table_separator(cells: &[&str]) -> bool {
+    cells
+        .iter()
+        .all(|cell| !cell.is_empty() && cell.chars().all(|c| c == '-' || c == ':' || c == ' '))
+}
+
+/// Renders the rows of a table like `| a | b |` with aligned columns. A separator row
+/// like `|---|---|` below the first row makes it a header.
+fn render_table(rows: &[&str]) -> Vec<String> {
+    let mut cells: Vec<Vec<&str>> = rows
+        .iter()
+        .map(|row| row[1..row.len() - 1].split('|').map(str::trim).collect())
+        .collect();
+
+    let has_header = cells.len() > 1 && is_table_separator(&cells[1]);
+    if has_header {
+        cells.remove(1);
+    }
+
+    let rendered: Vec<Vec<String>> = cells
+        .iter()
+        .map(|row| row.iter().map(|cell| render_inline(cell)).collect())
+        .collect();
+
+    let columns = rendered.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in &rendered {
+        for (column, cell) in row.iter().enumerate() {
+            widths[column] = widths[column].max(text_width(cell));
+        }
+    }
+
+    rendered
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    let padding = " ".repeat(widths[column] - text_width(cell));
+                    if has_header && index == 0 {
+                        format!("{}{}", style(cell).bold(), padding)
+                    } else {
+                        format!("{}{}", cell, padding)
+                    }
+                })
+                .collect();
+            format!("  {}", line.join("  ").trim_end())
+        })
+        .collect()
+}
+
+fn text_width(text: &str) -> usize {
+    strip_ansi_codes(text).chars().count()
+}
+
+/// Renders inline formatting, references like `T123` or `D123` and `@mentions`.
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if let Some((length, inline, inner)) = parse_delimited(&chars, i) {
+            output.push_str(&apply(inline, &inner));
+            i += length;
+            continue;
+        }
+
+        let at_boundary = i == 0 || !chars[i - 1].is_alphanumeric();
+
+        if chars[i] == '{' {
+            if let Some(length) = parse_reference(&chars, i + 1) {
+                if chars.get(i + 1 + length) == Some(&'}') {
+                    let reference: String = chars[i + 1..i + 1 + length].iter().collect();
+                    output.push_str(&style(reference).bold().cyan().to_string());
+                    i += length + 2;
+                    continue;
+                }
+            }
+        }
+
+        if at_boundary {
+            if let Some(length) = parse_reference(&chars, i) {
+                let reference: String = chars[i..i + length].iter().collect();
+                output.push_str(&style(reference).bold().cyan().to_string());
+                i += length;
+                continue;
+            }
+
+            if let Some(length) = parse_mention(&chars, i) {
+                let mention: String = chars[i..i + length].iter().collect();
+                output.push_str(&style(mention).bold().yellow().to_string());
+                i += length;
+                continue;
+            }
+        }
+
+        output.push(chars[i]);
+        i += 1;
+    }
+
+    output
+}
+
+/// Parses formatting like `**bold**` starting at `start`. Returns the length of the
+/// whole match, the formatting and the text inside the delimiters.
+fn parse_delimited(chars: &[char], start: usize) -> Option<(usize, Inline, String)> {
+    for &(delimiter, inline) in DELIMITERS.iter() {
+        let delimiter: Vec<char> = delimiter.chars().collect();
+        if !chars[start..].starts_with(&delimiter) {
+            continue;
+        }
+
+        // `//` in urls like https://phab.mycompany.com isn't italic.
+        if delimiter == ['/', '/'] && start > 0 && chars[start - 1] == ':' {
+            return None;
+        }
+
+        let content_start = start + delimiter.len();
+        let end =
+            (content_start..chars.len()).find(|&index| chars[index..].starts_with(&delimiter))?;
+
+        if end == content_start {
+            return None;
+        }
+
+        let inner: String = chars[content_start..end].iter().collect();
+        return Some((end + delimiter.len() - start, inline, inner));
+    }
+    None
+}
+
+fn apply(inline: Inline, text: &str) -> String {
+    let styled = style(text);
+    let styled = match inline {
+        Inline::Bold => styled.bold(),
+        Inline::Italic => styled.italic(),
+        Inline::Monospace => styled.cyan(),
+        Inline::Deleted => styled.dim(),
+        Inline::Underlined => styled.underlined(),
+    };
+    styled.to_string()
+}
+
+/// Parses references to tasks and revisions like `T123` or `D123`.
+fn parse_reference(chars: &[char], start: usize) -> Option<usize> {
+    match chars.get(start) {
+        Some('T') | Some('D') => {}
+        _ => return None,
+    }
+
+    let digits = chars[start + 1..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+
+    let next = chars.get(start + 1 + digits);
+    if digits == 0 || next.map_or(false, |c| c.is_alphanumeric()) {
+        return None;
+    }
+
+    Some(digits + 1)
+}
+
+/// Parses mentions of users like `@alice`.
+fn parse_mention(chars: &[char], start: usize) -> Option<usize> {
+    if chars.get(start) != Some(&'@') {
+        return None;
+    }
+
+    let mut length = chars[start + 1..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '-' || **c == '.')
+        .count();
+
+    // Punctuation at the end of a sentence isn't part of the username.
+    while length > 0 && chars[start + length] == '.' {
+        length -= 1;
+    }
+
+    if length == 0 {
+        None
+    } else {
+        Some(length + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_plain(text: &str) -> String {
+        strip_ansi_codes(&render(text)).to_string()
+    }
+
+    #[test]
+    fn test_render_headers_and_paragraphs() {
+        let document = "= Overview =\nThe app crashes on startup.\n\n== Steps\nOpen the app";
+
+        assert_eq!(
+            "Overview\nThe app crashes on startup.\n\nSteps\nOpen the app",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_inline_formatting() {
+        let document =
+            "This is **important**, //really// and `code` with ##mono## and ~~old~~ and __new__";
+
+        assert_eq!(
+            "This is important, really and code with mono and old and new",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_urls_are_not_italic() {
+        let document = "See https://phab.mycompany.com/T12 and http://example.com//path";
+
+        assert_eq!(
+            "See https://phab.mycompany.com/T12 and http://example.com//path",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_lists() {
+        let document = "- First\n- Second\n-- Nested\n  - Indented\n* Third\n\n# One\n# Two\n## Two.One\n# Three";
+
+        assert_eq!(
+            "  • First\n  • Second\n    • Nested\n    • Indented\n  • Third\n\n  1. One\n  2. Two\n    1. Two.One\n  3. Three",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_code_blocks() {
+        let document = "Run this:\n```lang=bash\ncargo build\n  cargo test\n```\nor\n\n  indented code\n  more code\nDone";
+
+        assert_eq!(
+            "Run this:\n    cargo build\n      cargo test\nor\n\n    indented code\n    more code\nDone",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_code_blocks_keep_formatting_characters() {
+        let document = "```\nlet x = **y**; // comment\n```";
+
+        assert_eq!("    let x = **y**; // comment", render_plain(document));
+    }
+
+    #[test]
+    fn test_render_tables() {
+        let document = "| Device | Crashes |\n|---|---|\n| Pixel | **Yes** |\n| iPhone 11 | No |";
+
+        assert_eq!(
+            "  Device     Crashes\n  Pixel      Yes\n  iPhone 11  No",
+            render_plain(document)
+        );
+    }
+
+    #[test]
+    fn test_render_references_and_mentions() {
+        let document = "@alice fixed T123 in {D456}, see DT12 and T12a. Thanks @bob.";
+
+        assert_eq!(
+            "@alice fixed T123 in D456, see DT12 and T12a. Thanks @bob.",
+            render_plain(document)
+        );
+
+        assert_eq!(
+            Some(4),
+            parse_reference(&"T123 ".chars().collect::<Vec<_>>(), 0)
+        );
+        assert_eq!(
+            None,
+            parse_reference(&"T12a".chars().collect::<Vec<_>>(), 0)
+        );
+        assert_eq!(
+            Some(4),
+            parse_mention(&"@bob.".chars().collect::<Vec<_>>(), 0)
+        );
+        assert_eq!(None, parse_mention(&"@ ".chars().collect::<Vec<_>>(), 0));
+    }
+
+    #[test]
+    fn test_render_quotes() {
+        assert_eq!("│ Quoted text", render_plain("> Quoted text"));
+    }
+
+    #[test]
+    fn test_render_styles() {
+        console::set_colors_enabled(true);
+
+        assert_eq!(
+            format!(
+                "{} {}",
+                style("T123").bold().cyan().force_styling(true),
+                style("bold").bold().force_styling(true)
+            ),
+            render("T123 **bold**")
+        );
+    }
+}
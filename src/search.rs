@@ -0,0 +1,117 @@
+use crate::diffs::search_diffs;
+use crate::output::{Output, Record};
+use crate::preferences::Preferences;
+use crate::structs::FabConfig;
+use crate::tasks::{search_tasks, TaskRecord};
+use crate::utils::parse_limit;
+use crate::NO_BORDER_PRESET;
+use anyhow::Error;
+use clap::ArgMatches;
+use comfy_table::{Attribute, Cell, ContentArrangement, Table};
+use futures::future::join;
+use serde::Serialize;
+use tokio::runtime::Runtime;
+
+/// Searches tasks and revisions of anyone, e.g. `fab search "crash on startup"`.
+pub fn process_search_command(
+    matches: &ArgMatches,
+    config: &FabConfig,
+    preferences: &Preferences,
+) -> Result<(), Error> {
+    let query = matches.value_of("query").unwrap();
+    let limit = parse_limit(matches)?;
+    let output = Output::from_matches(matches, preferences.default_columns.get("search"))?;
+    let search_type = matches.value_of("type").unwrap_or("all");
+
+    let include_tasks = search_type == "all" || search_type == "task";
+    let include_diffs = search_type == "all" || search_type == "diff";
+
+    let (tasks, revisions) = Runtime::new()?.block_on(join(
+        async {
+            if include_tasks {
+                search_tasks(query, limit, config).await
+            } else {
+                Ok(vec![])
+            }
+        },
+        async {
+            if include_diffs {
+                search_diffs(query, limit, config).await
+            } else {
+                Ok(vec![])
+            }
+        },
+    ));
+
+    let tasks = tasks?;
+    let revisions = revisions?;
+
+    if !output.is_default_table() {
+        let mut records: Vec<SearchResultRecord> = tasks
+            .iter()
+            .map(|task| TaskRecord::new(task, config))
+            .map(|task| SearchResultRecord {
+                kind: "task",
+                id: format!("T{}", task.id),
+                title: task.title,
+                status: task.status,
+                url: task.url,
+            })
+            .collect();
+        records.extend(revisions.iter().map(|revision| SearchResultRecord {
+            kind: "diff",
+            id: format!("D{}", revision.id),
+            title: revision.fields.title.clone(),
+            status: revision.fields.status.name.clone(),
+            url: revision.url(config),
+        }));
+        return output.print(&records);
+    }
+
+    let mut table = Table::new();
+
+    table
+        .load_preset(NO_BORDER_PRESET)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    for task in tasks {
+        let mut row = vec![Cell::new("Task").add_attribute(Attribute::Bold)];
+        row.extend(task.get_cells(config));
+        table.add_row(row);
+    }
+
+    for revision in revisions {
+        table.add_row(vec![
+            Cell::new("Revision").add_attribute(Attribute::Bold),
+            revision.get_status_cell(),
+            Cell::new(&revision.fields.title),
+            Cell::new(&revision.url(config)).add_attribute(Attribute::Bold),
+        ]);
+    }
+
+    println!("{}", table);
+    Ok(())
+}
+
+/// A task or revision found by `fab search`, as printed with `--format`. See
+/// `output::Record`.
+#[derive(Debug, Serialize)]
+struct SearchResultRecord {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: String,
+    title: String,
+    status: String,
+    url: String,
+}
+
+impl Record for SearchResultRecord {
+    const FIELDS: &'static [&'static str] = &["type", "id", "title", "status", "url"];
+
+    fn cell(&self, field: &str, text: String) -> Cell {
+        match field {
+            "type" | "url" => Cell::new(text).add_attribute(Attribute::Bold),
+            _ => Cell::new(text),
+        }
+    }
+}
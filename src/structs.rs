@@ -1,21 +1,29 @@
-use comfy_table::Color;
+use comfy_table::{Attribute, Cell, CellAlignment, Color};
+use fab::conduit::ConduitClient;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+/// The instance and user fab works with, along with the client to talk to it.
+#[derive(Debug, Clone)]
 pub struct FabConfig {
     pub hosted_instance: String,
-    pub api_token: String,
     pub phid: String,
+    /// Shared by every request, so that they reuse the same connections and pick
+    /// up a renewed API token.
+    client: ConduitClient,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct RevisionData {
-    pub data: Vec<Revision>,
-}
+impl FabConfig {
+    pub fn new(client: ConduitClient, phid: String) -> FabConfig {
+        FabConfig {
+            hosted_instance: client.hosted_instance().to_string(),
+            phid,
+            client,
+        }
+    }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub struct WhoAmIResponse {
-    pub result: UserResponse,
+    pub fn client(&self) -> &ConduitClient {
+        &self.client
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -25,10 +33,14 @@ pub struct UserResponse {
     pub user_name: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct Revision {
     pub id: i32,
+    #[serde(default)]
+    pub phid: String,
     pub fields: Fields,
+    #[serde(default)]
+    pub attachments: RevisionAttachments,
 }
 
 impl Revision {
@@ -36,45 +48,207 @@ impl Revision {
         return format!("{}D{}", &config.hosted_instance, &self.id);
     }
 
+    pub fn get_status_cell(&self) -> Cell {
+        Revision::status_cell(&self.fields.status.name)
+    }
+
+    /// A cell for the name of a revision status, colored like in `fab diffs`.
+    pub fn status_cell(status: &str) -> Cell {
+        Cell::new(status)
+            .bg(status_background(status))
+            .fg(status_foreground(status))
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold)
+    }
+
     pub fn get_background(&self) -> Color {
-        let status = &self.fields.status.name;
-        if status.eq("Needs Review") {
-            Color::Magenta
-        } else if status.eq("Accepted") {
-            Color::Green
-        } else if status.eq("Needs Revision") || status.eq("Changes Planned") {
-            Color::Red
-        } else {
-            Color::Yellow
-        }
+        status_background(&self.fields.status.name)
     }
 
     pub fn get_foreground(&self) -> Color {
-        let status = &self.fields.status.name;
-        if status.eq("Needs Review") {
-            Color::White
-        } else if status.eq("Accepted") {
-            Color::Black
-        } else if status.eq("Needs Revision") || status.eq("Changes Planned") {
-            Color::White
-        } else {
-            Color::Black
-        }
+        status_foreground(&self.fields.status.name)
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+fn status_background(status: &str) -> Color {
+    if status.eq("Needs Review") {
+        Color::Magenta
+    } else if status.eq("Accepted") {
+        Color::Green
+    } else if status.eq("Needs Revision") || status.eq("Changes Planned") {
+        Color::Red
+    } else {
+        Color::Yellow
+    }
+}
+
+fn status_foreground(status: &str) -> Color {
+    if status.eq("Needs Review") {
+        Color::White
+    } else if status.eq("Accepted") {
+        Color::Black
+    } else if status.eq("Needs Revision") || status.eq("Changes Planned") {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct Fields {
     pub title: String,
     pub status: Status,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default, rename = "testPlan")]
+    pub test_plan: String,
+    #[serde(default, rename = "authorPHID")]
+    pub author_phid: String,
+    #[serde(default, rename = "repositoryPHID")]
+    pub repository_phid: Option<String>,
+    #[serde(default, rename = "diffPHID")]
+    pub diff_phid: String,
+    #[serde(default, rename = "dateCreated")]
+    pub date_created: i64,
+    #[serde(default, rename = "dateModified")]
+    pub date_modified: i64,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Default)]
 pub struct Status {
     pub name: String,
     pub closed: bool,
 }
 
+/// Attachments requested through `attachments[...]` in `differential.revision.search`.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct RevisionAttachments {
+    pub reviewers: Option<ReviewersAttachment>,
+    pub subscribers: Option<SubscribersAttachment>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReviewersAttachment {
+    pub reviewers: Vec<Reviewer>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Reviewer {
+    #[serde(rename = "reviewerPHID")]
+    pub reviewer_phid: String,
+    pub status: String,
+    #[serde(rename = "isBlocking")]
+    pub is_blocking: bool,
+}
+
+impl Reviewer {
+    pub fn get_background(&self) -> Color {
+        match self.status.as_str() {
+            "accepted" => Color::Green,
+            "rejected" | "blocking" => Color::Red,
+            "resigned" => Color::Grey,
+            _ => Color::Yellow,
+        }
+    }
+
+    pub fn get_foreground(&self) -> Color {
+        match self.status.as_str() {
+            "rejected" | "blocking" => Color::White,
+            _ => Color::Black,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SubscribersAttachment {
+    #[serde(rename = "subscriberPHIDs")]
+    pub subscriber_phids: Vec<String>,
+    #[serde(rename = "subscriberCount")]
+    pub subscriber_count: i32,
+}
+
+/// A transaction on an object as returned by `transaction.search`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TransactionItem {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub transaction_type: Option<String>,
+    #[serde(rename = "authorPHID")]
+    pub author_phid: String,
+    #[serde(rename = "dateCreated")]
+    pub date_created: i64,
+    pub comments: Vec<TransactionComment>,
+    /// Type specific fields. Conduit sends an empty list when there aren't any, so
+    /// this is kept as a raw value.
+    #[serde(default)]
+    pub fields: serde_json::Value,
+}
+
+impl TransactionItem {
+    pub fn is_inline(&self) -> bool {
+        self.transaction_type.as_deref() == Some("inline")
+    }
+
+    /// The latest, non-removed text of the comment attached to the transaction.
+    pub fn comment(&self) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|comment| !comment.removed)
+            .map(|comment| comment.content.raw.as_str())
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.fields.get("path").and_then(|path| path.as_str())
+    }
+
+    pub fn line(&self) -> Option<i64> {
+        self.fields.get("line").and_then(|line| line.as_i64())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TransactionComment {
+    pub removed: bool,
+    pub content: CommentContent,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CommentContent {
+    pub raw: String,
+}
+
+/// A single diff of a revision, as returned by `differential.diff.search`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Diff {
+    pub id: i32,
+    pub phid: String,
+    pub fields: DiffFields,
+}
+
+impl Diff {
+    /// The name of the branch the diff was created from, if it was uploaded with one.
+    pub fn branch(&self) -> Option<&str> {
+        self.fields
+            .refs
+            .iter()
+            .find(|diff_ref| diff_ref.ref_type.eq("branch"))
+            .and_then(|diff_ref| diff_ref.name.as_deref())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DiffFields {
+    #[serde(default)]
+    pub refs: Vec<DiffRef>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DiffRef {
+    #[serde(rename = "type")]
+    pub ref_type: String,
+    pub name: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -90,7 +264,9 @@ mod tests {
                     name: String::from("Accepted"),
                     closed: false,
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Green, revision.get_background());
@@ -107,7 +283,9 @@ mod tests {
                     name: String::from("Needs Revision"),
                     closed: false,
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Red, revision.get_background());
@@ -124,10 +302,53 @@ mod tests {
                     name: String::from("Needs Review"),
                     closed: false,
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Magenta, revision.get_background());
         assert_eq!(Color::White, revision.get_foreground());
     }
+
+    #[test]
+    fn test_reviewer_colors() {
+        let reviewer = Reviewer {
+            reviewer_phid: String::from("PHID-USER-1"),
+            status: String::from("accepted"),
+            is_blocking: false,
+        };
+        assert_eq!(Color::Green, reviewer.get_background());
+        assert_eq!(Color::Black, reviewer.get_foreground());
+
+        let reviewer = Reviewer {
+            reviewer_phid: String::from("PHID-USER-1"),
+            status: String::from("blocking"),
+            is_blocking: true,
+        };
+        assert_eq!(Color::Red, reviewer.get_background());
+        assert_eq!(Color::White, reviewer.get_foreground());
+    }
+
+    #[test]
+    fn test_diff_branch() {
+        let diff = Diff {
+            id: 10,
+            phid: String::from("PHID-DIFF-1"),
+            fields: DiffFields {
+                refs: vec![
+                    DiffRef {
+                        ref_type: String::from("base"),
+                        name: None,
+                    },
+                    DiffRef {
+                        ref_type: String::from("branch"),
+                        name: Some(String::from("feature")),
+                    },
+                ],
+            },
+        };
+
+        assert_eq!(Some("feature"), diff.branch());
+    }
 }
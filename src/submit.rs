@@ -0,0 +1,139 @@
+use crate::commit_message::CommitMessage;
+use crate::diffs::{edit_revision_with_identifier, get_revision_record};
+use crate::errors::InvalidArgument;
+use crate::output::{self, Format};
+use crate::structs::FabConfig;
+use crate::{git, users};
+use anyhow::Error;
+use clap::ArgMatches;
+use fab::conduit::{Params, Transaction};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+const CREATE_RAW_DIFF: &str = "differential.createrawdiff";
+
+/// Uploads the changes of the current branch and creates or updates a revision for them.
+pub fn process_submit_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let base = match matches.value_of("base") {
+        Some(base) => base.to_string(),
+        None => git::default_base().ok_or_else(|| {
+            InvalidArgument(
+                "Couldn't find the default branch of origin. Pass the branch to diff against with --base"
+                    .to_string(),
+            )
+        })?,
+    };
+    let format = Format::from_matches(matches)?;
+
+    let branch = git::current_branch()?;
+
+    let raw_diff = git::diff_against(&base)?;
+    let messages: Vec<CommitMessage> = git::branch_commit_messages(&base)?
+        .iter()
+        .map(|message| CommitMessage::parse(message))
+        .collect();
+    if raw_diff.trim().is_empty() || messages.is_empty() {
+        return Err(InvalidArgument(format!(
+            "{} has no changes against {} to submit",
+            branch, base
+        ))
+        .into());
+    }
+
+    // The commit with the trailer describes an existing revision, and the first
+    // commit of the branch a new one.
+    let existing_revision = messages
+        .iter()
+        .find_map(|message| message.revision_id(&config.hosted_instance));
+    let message = messages
+        .iter()
+        .find(|message| message.revision_id(&config.hosted_instance).is_some())
+        .unwrap_or_else(|| messages.last().unwrap());
+
+    if message.title.is_empty() {
+        return Err(InvalidArgument(format!(
+            "The commit message of the revision on {} needs a title",
+            branch
+        ))
+        .into());
+    }
+
+    let mut reviewers: Vec<&String> = messages
+        .iter()
+        .flat_map(|message| message.reviewers.iter())
+        .collect();
+    reviewers.sort();
+    reviewers.dedup();
+
+    let mut runtime = Runtime::new()?;
+
+    let diff = runtime.block_on(create_raw_diff(config, &raw_diff))?;
+
+    let mut reviewer_phids = Vec::with_capacity(reviewers.len());
+    for reviewer in reviewers {
+        let user = runtime.block_on(users::get_user(reviewer, config))?;
+        reviewer_phids.push(Value::from(user.phid));
+    }
+
+    let mut transactions = vec![
+        Transaction::new("update", Value::from(diff.phid)),
+        Transaction::new("title", Value::from(message.title.clone())),
+        Transaction::new("summary", Value::from(message.summary.clone())),
+        Transaction::new("testPlan", Value::from(message.test_plan.clone())),
+    ];
+
+    if !reviewer_phids.is_empty() {
+        // Only add reviewers to existing revisions so that reviewers who were
+        // added through the web UI aren't dropped.
+        let kind = if existing_revision.is_some() {
+            "reviewers.add"
+        } else {
+            "reviewers.set"
+        };
+        transactions.push(Transaction::new(kind, Value::Array(reviewer_phids)));
+    }
+
+    let object_identifier = existing_revision.map(|id| format!("D{}", id));
+
+    let result = runtime.block_on(edit_revision_with_identifier(
+        config,
+        object_identifier.as_deref(),
+        &transactions,
+    ))?;
+
+    let url = format!("{}D{}", config.hosted_instance, result.object.id);
+
+    // The trailer goes to HEAD, which is the only commit that can be amended without
+    // rewriting the branch. Later submits find it there.
+    if existing_revision.is_none() {
+        git::amend_commit_message(&messages[0].with_revision_url(&url))?;
+    }
+
+    if format != Format::Table {
+        let record = runtime.block_on(get_revision_record(config, result.object.id))?;
+        return output::print_record(format, &record);
+    }
+
+    if existing_revision.is_some() {
+        println!("Updated revision {}", url);
+    } else {
+        println!("Created revision {}", url);
+    }
+
+    Ok(())
+}
+
+async fn create_raw_diff(config: &FabConfig, raw_diff: &str) -> Result<RawDiff, Error> {
+    let params = Params::new().set("diff", raw_diff);
+
+    config
+        .client()
+        .call::<RawDiff>(CREATE_RAW_DIFF, &params)
+        .await
+}
+
+#[derive(Deserialize, Debug)]
+struct RawDiff {
+    phid: String,
+}
@@ -1,14 +1,19 @@
-use crate::diffs::{get_authored_diffs, get_needs_review_diffs, render_diffs};
+use crate::builds::get_build_statuses;
+use crate::diffs::{get_authored_diffs, get_needs_review_diffs, render_diffs, revision_records};
+use crate::errors::InvalidArgument;
+use crate::output::{self, Format};
 use crate::preferences::Preferences;
 use crate::structs::FabConfig;
-use crate::tasks::{get_tasks, render_tasks, Priority};
+use crate::tasks::{get_tasks, render_tasks, Priority, TaskFilter, TaskRecord};
+use crate::utils::parse_limit_value;
 use anyhow::Error;
 use clap::ArgMatches;
 use console::style;
+use fab::conduit::Limit;
 use futures::future::join3;
 
 pub fn process_summary(
-    _matches: &ArgMatches,
+    matches: &ArgMatches,
     config: &FabConfig,
     preferences: &Preferences,
 ) -> Result<(), Error> {
@@ -18,33 +23,80 @@ pub fn process_summary(
         .map(|priority| Priority::get_value_for_name(&priority).unwrap())
         .collect();
 
+    let format = Format::from_matches(matches)?;
+    if format == Format::Csv || format == Format::Tsv {
+        return Err(InvalidArgument(format!(
+            "The summary has several sections and can't be printed as {:?}. Use json or markdown instead",
+            format
+        ))
+        .into());
+    }
+
     let status = "open";
+    let limit = parse_limit_value(&preferences.default_limit_str)?;
 
     let result = tokio::runtime::Runtime::new()?.block_on(join3(
-        get_needs_review_diffs(config),
-        get_authored_diffs(config),
+        get_needs_review_diffs(config, Limit::All),
+        get_authored_diffs(config, Limit::All),
         get_tasks(
-            preferences.default_limit_str.as_str(),
+            limit,
             &priorities,
             &preferences.default_sort,
-            &status,
+            Some(status),
+            &TaskFilter::default(),
             config,
         ),
     ));
 
+    let needs_review = result.0?;
+    let authored = result.1?;
+
+    // One lookup for the builds of both lists.
+    let build_statuses = tokio::runtime::Runtime::new()?.block_on(get_build_statuses(
+        config,
+        &needs_review.iter().chain(&authored).collect::<Vec<_>>(),
+    ));
+    let tasks = result.2?;
+
+    if format != Format::Table {
+        let needs_review = revision_records(config, &needs_review, &build_statuses);
+        let authored = revision_records(config, &authored, &build_statuses);
+        let tasks: Vec<TaskRecord> = tasks
+            .iter()
+            .map(|task| TaskRecord::new(task, config))
+            .collect();
+
+        if format == Format::Json {
+            let summary = json!({
+                "needs_review": needs_review,
+                "authored": authored,
+                "tasks": tasks,
+            });
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        } else {
+            println!("## Diffs that need your review\n");
+            println!("{}\n", output::format_records(format, &needs_review)?);
+            println!("## Your open diffs\n");
+            println!("{}\n", output::format_records(format, &authored)?);
+            println!("## Tasks that need your attention\n");
+            println!("{}", output::format_records(format, &tasks)?);
+        }
+        return Ok(());
+    }
+
     println!(
         "{}",
         style("Diffs that need your review").bold().underlined()
     );
     println!();
 
-    render_diffs(config, &result.0?);
+    render_diffs(config, &needs_review, &build_statuses);
     println!();
 
     println!("{}", style("Your open diffs").bold().underlined());
     println!();
 
-    render_diffs(config, &result.1?);
+    render_diffs(config, &authored, &build_statuses);
     println!();
 
     println!(
@@ -52,6 +104,6 @@ pub fn process_summary(
         style("Tasks that need your attention").bold().underlined()
     );
     println!();
-    render_tasks(&result.2?, config);
+    render_tasks(&tasks, config);
     Ok(())
 }
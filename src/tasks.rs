@@ -1,49 +1,136 @@
+use crate::errors::{finish_quietly, InvalidArgument, NotFound};
+use crate::output::{self, Format, Output, Record};
+use crate::phids::Handle;
 use crate::preferences::Preferences;
-use crate::structs::FabConfig;
-use crate::{auth, NO_BORDER_PRESET};
+use crate::structs::{FabConfig, SubscribersAttachment};
+use crate::utils::{format_timestamp, parse_limit, parse_object_id, read_message};
+use crate::{edges, phids, projects, remarkup, users, NO_BORDER_PRESET};
 use anyhow::{anyhow, Error};
 use clap::ArgMatches;
 use comfy_table::{Attribute, Cell, CellAlignment, Color, ContentArrangement, Table};
-use serde::Deserialize;
-use serde_json::{Map, Value};
+use console::style;
+use fab::conduit::{EditResult, Limit, Search, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::runtime::Runtime;
 
-const MANIPHEST_SEARCH: &str = "api/maniphest.search";
+const MANIPHEST_SEARCH: &str = "maniphest.search";
+const MANIPHEST_EDIT: &str = "maniphest.edit";
+
+/// Narrows down tasks to a project, and optionally to a column of its workboard,
+/// or to a saved query. Without either, only tasks assigned to the user are returned.
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    pub project_phid: Option<String>,
+    pub column_phid: Option<String>,
+    /// Key of a saved query of the Phabricator web UI, like `assigned` or `abc123XYZ`.
+    pub query_key: Option<String>,
+}
 
 pub async fn get_tasks(
-    limit: &str,
+    limit: Limit,
     priorities: &[i32],
     order: &str,
-    status: &str,
+    status: Option<&str>,
+    filter: &TaskFilter,
     config: &FabConfig,
 ) -> Result<Vec<Maniphest>, Error> {
-    let mut map = Map::new();
-    map.insert("queryKey".to_string(), Value::from("assigned"));
-    map.insert(
-        "api.token".to_string(),
-        Value::from(config.api_token.clone()),
-    );
-    map.insert("limit".to_string(), Value::from(limit));
-    map.insert("constraints[statuses][0]".to_string(), Value::from(status));
-    map.insert("order".to_string(), Value::from(order));
-
-    for (i, &priority) in priorities.iter().enumerate() {
-        map.insert(
-            format!("constraints[priorities][{}]", i),
-            Value::from(priority),
-        );
+    let mut search = task_search();
+
+    if let Some(project_phid) = &filter.project_phid {
+        search = search
+            .constraints("projects", &[project_phid.as_str()])
+            .attachment("columns");
     }
+    if let Some(column_phid) = &filter.column_phid {
+        search = search.constraints("columnPHIDs", &[column_phid.as_str()]);
+    }
+    match (&filter.query_key, &filter.project_phid) {
+        (Some(query_key), _) => search = search.query_key(query_key),
+        (None, None) => search = search.query_key("assigned"),
+        (None, Some(_)) => {}
+    }
+    if let Some(status) = status {
+        search = search.constraints("statuses", &[status]);
+    }
+    search = search.order(order).constraints("priorities", priorities);
 
-    let json_body = Value::Object(map);
+    config.client().search(&search, limit).await
+}
 
-    let url = format!("{}{}", &config.hosted_instance, MANIPHEST_SEARCH);
+/// Searches tasks along with their projects and subscribers, which are part of
+/// `TaskRecord`.
+fn task_search() -> Search {
+    Search::new(MANIPHEST_SEARCH)
+        .attachment("projects")
+        .attachment("subscribers")
+}
 
-    let result = auth::send::<ManiphestSearchData>(
-        config,
-        reqwest::Client::new().post(&url).form(&json_body),
-    )
-    .await?;
+/// Get tasks of anyone that match the full-text query.
+pub async fn search_tasks(
+    query: &str,
+    limit: Limit,
+    config: &FabConfig,
+) -> Result<Vec<Maniphest>, Error> {
+    let search = task_search().constraint("query", query);
+
+    config.client().search(&search, limit).await
+}
 
-    Ok(result.data)
+/// Get a single task by its id along with its projects and subscribers.
+pub async fn get_task(config: &FabConfig, id: i32) -> Result<Maniphest, Error> {
+    let search = task_search().constraints("ids", &[id]);
+
+    config
+        .client()
+        .search::<Maniphest>(&search, Limit::Count(1))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| NotFound(format!("Could not find T{}", id)).into())
+}
+
+/// Get a single task as printed with `--format`, e.g. after it was edited.
+pub async fn get_task_record(config: &FabConfig, id: i32) -> Result<TaskRecord, Error> {
+    Ok(TaskRecord::new(&get_task(config, id).await?, config))
+}
+
+/// Apply the given transactions to a task.
+pub async fn edit_task(
+    config: &FabConfig,
+    id: i32,
+    transactions: &[Transaction],
+) -> Result<EditResult, Error> {
+    edit_task_with_identifier(config, Some(&format!("T{}", id)), transactions).await
+}
+
+/// Apply the given transactions to the task with the given identifier, or create
+/// a new task if there is none.
+pub async fn edit_task_with_identifier(
+    config: &FabConfig,
+    object_identifier: Option<&str>,
+    transactions: &[Transaction],
+) -> Result<EditResult, Error> {
+    config
+        .client()
+        .edit(MANIPHEST_EDIT, object_identifier, transactions)
+        .await
+}
+
+/// Prints the tasks as the default table, or as chosen with `--format`,
+/// `--columns` or `--template`.
+pub fn print_tasks(output: &Output, tasks: &[Maniphest], config: &FabConfig) -> Result<(), Error> {
+    if output.is_default_table() {
+        render_tasks(tasks, config);
+        return Ok(());
+    }
+
+    let records: Vec<TaskRecord> = tasks
+        .iter()
+        .map(|task| TaskRecord::new(task, config))
+        .collect();
+    output.print(&records)
 }
 
 pub fn render_tasks(tasks: &[Maniphest], config: &FabConfig) {
@@ -54,15 +141,7 @@ pub fn render_tasks(tasks: &[Maniphest], config: &FabConfig) {
         .set_content_arrangement(ContentArrangement::Dynamic);
 
     for task in tasks {
-        table.add_row(vec![
-            Cell::new(&task.fields.priority.name)
-                .bg(task.get_background())
-                .fg(task.get_foreground())
-                .set_alignment(CellAlignment::Center)
-                .add_attribute(Attribute::Bold),
-            Cell::new(&task.fields.name),
-            Cell::new(&task.get_task_url(config)).add_attribute(Attribute::Bold),
-        ]);
+        table.add_row(task.get_cells(config));
     }
 
     println!("{}", table)
@@ -76,99 +155,736 @@ pub fn process_task_command(
     process_list_tasks(matches, config, preferences)
 }
 
+/// Processes the commands that work on a single task, like `fab task create`.
+pub fn process_single_task_command(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    if let Some(matches) = matches.subcommand_matches("create") {
+        return process_create_task(matches, config);
+    }
+    if let Some(matches) = matches.subcommand_matches("edit") {
+        return process_edit_task(matches, config);
+    }
+    if let Some(id) = matches.value_of("id") {
+        let format = Format::from_matches(matches)?;
+        return process_task_detail(parse_object_id('T', id)?, format, config);
+    }
+    Ok(())
+}
+
+/// Shows the details of a single task given by `fab task <ID>`.
+fn process_task_detail(id: i32, format: Format, config: &FabConfig) -> Result<(), Error> {
+    let mut runtime = Runtime::new()?;
+
+    let task = runtime.block_on(get_task(config, id))?;
+
+    if format != Format::Table {
+        return output::print_record(format, &TaskRecord::new(&task, config));
+    }
+
+    let relations = runtime.block_on(get_task_relations(config, &task.phid))?;
+
+    let mut related = relations.related_phids();
+    related.push(task.fields.author_phid.clone());
+    if let Some(owner_phid) = &task.fields.owner_phid {
+        related.push(owner_phid.clone());
+    }
+    if let Some(projects) = &task.attachments.projects {
+        related.extend(projects.project_phids.iter().cloned());
+    }
+    if let Some(subscribers) = &task.attachments.subscribers {
+        related.extend(subscribers.subscriber_phids.iter().cloned());
+    }
+    related.sort();
+    related.dedup();
+
+    let handles = runtime.block_on(phids::get_handles(&related, config))?;
+
+    render_task_detail(&task, &relations, &handles, config);
+    Ok(())
+}
+
+/// How deep parent and subtask trees are followed.
+const MAX_TREE_DEPTH: usize = 5;
+
+/// Parents, subtasks and revisions of a task.
+struct TaskRelations {
+    /// Parent tasks, starting with the direct parent.
+    parents: Vec<String>,
+    /// Subtasks of the task and of its subtasks, keyed by their parent.
+    subtasks: HashMap<String, Vec<String>>,
+    revisions: Vec<String>,
+}
+
+impl TaskRelations {
+    fn related_phids(&self) -> Vec<String> {
+        let mut related = self.parents.clone();
+        related.extend(self.subtasks.values().flatten().cloned());
+        related.extend(self.revisions.iter().cloned());
+        related
+    }
+}
+
+async fn get_task_relations(config: &FabConfig, phid: &str) -> Result<TaskRelations, Error> {
+    let task_edges = edges::get_edges(
+        config,
+        &[phid.to_string()],
+        &[
+            edges::TASK_PARENT,
+            edges::TASK_SUBTASK,
+            edges::TASK_REVISION,
+        ],
+    )
+    .await?;
+
+    let destinations = |edge_type: &str| -> Vec<String> {
+        task_edges
+            .iter()
+            .filter(|edge| edge.edge_type == edge_type)
+            .map(|edge| edge.destination_phid.clone())
+            .collect()
+    };
+
+    let revisions = destinations(edges::TASK_REVISION);
+
+    // Follow the first parent up the tree.
+    let mut parents = vec![];
+    let mut next_parent = destinations(edges::TASK_PARENT).into_iter().next();
+    while let Some(parent) = next_parent {
+        if parents.contains(&parent) || parents.len() >= MAX_TREE_DEPTH {
+            break;
+        }
+        next_parent = edges::get_edges(config, &[parent.clone()], &[edges::TASK_PARENT])
+            .await?
+            .into_iter()
+            .next()
+            .map(|edge| edge.destination_phid);
+        parents.push(parent);
+    }
+
+    // Follow the subtasks down the tree, one level at a time.
+    let mut subtasks = HashMap::new();
+    subtasks.insert(phid.to_string(), destinations(edges::TASK_SUBTASK));
+    let mut level = subtasks[phid].clone();
+    for _ in 1..MAX_TREE_DEPTH {
+        let level_edges = edges::get_edges(config, &level, &[edges::TASK_SUBTASK]).await?;
+        level = vec![];
+        for edge in level_edges {
+            if subtasks.contains_key(&edge.destination_phid) {
+                continue;
+            }
+            level.push(edge.destination_phid.clone());
+            subtasks
+                .entry(edge.source_phid)
+                .or_insert_with(Vec::new)
+                .push(edge.destination_phid);
+        }
+        if level.is_empty() {
+            break;
+        }
+    }
+
+    Ok(TaskRelations {
+        parents,
+        subtasks,
+        revisions,
+    })
+}
+
+/// Lines of the tree below `root`, indented by their depth.
+fn get_tree_lines(
+    root: &str,
+    children: &HashMap<String, Vec<String>>,
+    handles: &HashMap<String, Handle>,
+    depth: usize,
+) -> Vec<String> {
+    let mut lines = vec![];
+    if depth > MAX_TREE_DEPTH {
+        return lines;
+    }
+
+    for child in children.get(root).into_iter().flatten() {
+        lines.push(format!(
+            "{}{}",
+            "  ".repeat(depth),
+            get_full_name(handles, child)
+        ));
+        lines.extend(get_tree_lines(child, children, handles, depth + 1));
+    }
+    lines
+}
+
+fn get_full_name(handles: &HashMap<String, Handle>, phid: &str) -> String {
+    match handles.get(phid) {
+        Some(handle) if handle.status == "closed" => format!("{} (Closed)", handle.full_name),
+        Some(handle) => handle.full_name.clone(),
+        None => phid.to_string(),
+    }
+}
+
+fn render_task_detail(
+    task: &Maniphest,
+    relations: &TaskRelations,
+    handles: &HashMap<String, Handle>,
+    config: &FabConfig,
+) {
+    println!(
+        "{}",
+        style(format!("T{}: {}", task.id, task.fields.name)).bold()
+    );
+    println!();
+
+    let mut table = Table::new();
+
+    table
+        .load_preset(NO_BORDER_PRESET)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new("Status").add_attribute(Attribute::Bold),
+        Cell::new(&task.fields.status.name),
+    ]);
+    table.add_row(vec![
+        Cell::new("Priority").add_attribute(Attribute::Bold),
+        Cell::new(&task.fields.priority.name)
+            .bg(task.get_background())
+            .fg(task.get_foreground())
+            .set_alignment(CellAlignment::Center)
+            .add_attribute(Attribute::Bold),
+    ]);
+    table.add_row(vec![
+        Cell::new("Author").add_attribute(Attribute::Bold),
+        Cell::new(phids::get_name(handles, &task.fields.author_phid)),
+    ]);
+    table.add_row(vec![
+        Cell::new("Assigned").add_attribute(Attribute::Bold),
+        Cell::new(match &task.fields.owner_phid {
+            Some(owner_phid) => phids::get_name(handles, owner_phid),
+            None => String::from("Up for grabs"),
+        }),
+    ]);
+    if let Some(projects) = &task.attachments.projects {
+        let names: Vec<String> = projects
+            .project_phids
+            .iter()
+            .map(|phid| phids::get_name(handles, phid))
+            .collect();
+        table.add_row(vec![
+            Cell::new("Projects").add_attribute(Attribute::Bold),
+            Cell::new(names.join(", ")),
+        ]);
+    }
+    if let Some(subscribers) = &task.attachments.subscribers {
+        let names: Vec<String> = subscribers
+            .subscriber_phids
+            .iter()
+            .map(|phid| phids::get_name(handles, phid))
+            .collect();
+        table.add_row(vec![
+            Cell::new("Subscribers").add_attribute(Attribute::Bold),
+            Cell::new(names.join(", ")),
+        ]);
+    }
+    match &task.fields.points {
+        Some(Value::Number(points)) => {
+            table.add_row(vec![
+                Cell::new("Points").add_attribute(Attribute::Bold),
+                Cell::new(points),
+            ]);
+        }
+        Some(Value::String(points)) => {
+            table.add_row(vec![
+                Cell::new("Points").add_attribute(Attribute::Bold),
+                Cell::new(points),
+            ]);
+        }
+        _ => {}
+    }
+    table.add_row(vec![
+        Cell::new("Created").add_attribute(Attribute::Bold),
+        Cell::new(format_timestamp(task.fields.date_created)),
+    ]);
+    table.add_row(vec![
+        Cell::new("Updated").add_attribute(Attribute::Bold),
+        Cell::new(format_timestamp(task.fields.date_modified)),
+    ]);
+    table.add_row(vec![
+        Cell::new("URL").add_attribute(Attribute::Bold),
+        Cell::new(task.get_task_url(config)).add_attribute(Attribute::Bold),
+    ]);
+    println!("{}", table);
+    println!();
+
+    if !relations.parents.is_empty() {
+        println!("{}", style("Parent tasks").bold().underlined());
+        println!();
+        for (depth, parent) in relations.parents.iter().rev().enumerate() {
+            println!("{}{}", "  ".repeat(depth), get_full_name(handles, parent));
+        }
+        println!();
+    }
+
+    let subtasks = get_tree_lines(&task.phid, &relations.subtasks, handles, 0);
+    if !subtasks.is_empty() {
+        println!("{}", style("Subtasks").bold().underlined());
+        println!();
+        for line in subtasks {
+            println!("{}", line);
+        }
+        println!();
+    }
+
+    if !relations.revisions.is_empty() {
+        println!("{}", style("Revisions").bold().underlined());
+        println!();
+        for revision in &relations.revisions {
+            println!("{}", get_full_name(handles, revision));
+        }
+        println!();
+    }
+
+    if !task.fields.description.raw.is_empty() {
+        println!("{}", style("Description").bold().underlined());
+        println!();
+        println!("{}", remarkup::render(&task.fields.description.raw));
+        println!();
+    }
+}
+
+fn process_edit_task(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let id = parse_object_id('T', matches.value_of("id").unwrap())?;
+    let format = Format::from_matches(matches)?;
+
+    let mut transactions = vec![];
+
+    if let Some(title) = matches.value_of("title") {
+        transactions.push(Transaction::new("title", Value::from(title)));
+    }
+
+    if let Some(status) = matches.value_of("status") {
+        transactions.push(Transaction::new("status", Value::from(status)));
+    }
+
+    if let Some(priority) = matches.value_of("priority") {
+        let value = Priority::get_value_for_name(priority)?;
+        transactions.push(Transaction::new(
+            "priority",
+            Value::from(Priority::get_keyword_for_value(value)?),
+        ));
+    }
+
+    let mut runtime = Runtime::new()?;
+
+    if matches.is_present("claim") {
+        transactions.push(Transaction::new("owner", Value::from(config.phid.clone())));
+    } else if let Some(assignee) = matches.value_of("assign") {
+        let user = runtime.block_on(users::get_user(assignee, config))?;
+        transactions.push(Transaction::new("owner", Value::from(user.phid)));
+    }
+
+    for &(arg, kind) in &[
+        ("add-project", "projects.add"),
+        ("remove-project", "projects.remove"),
+    ] {
+        if let Some(project_names) = matches.values_of(arg) {
+            let mut phids = vec![];
+            for project in project_names {
+                let project = runtime.block_on(projects::get_project(project, config))?;
+                phids.push(Value::from(project.phid));
+            }
+            transactions.push(Transaction::new(kind, Value::Array(phids)));
+        }
+    }
+
+    if transactions.is_empty() {
+        return Err(InvalidArgument(
+            "Nothing to edit. See `fab task edit --help` for what can be changed".to_string(),
+        )
+        .into());
+    }
+
+    runtime.block_on(edit_task(config, id, &transactions))?;
+
+    if format != Format::Table {
+        return output::print_record(format, &runtime.block_on(get_task_record(config, id))?);
+    }
+
+    println!("Updated task {}", Maniphest::get_url_for_id(config, id));
+    Ok(())
+}
+
+fn process_create_task(matches: &ArgMatches, config: &FabConfig) -> Result<(), Error> {
+    let title = matches.value_of("title").unwrap();
+    let format = Format::from_matches(matches)?;
+
+    let mut transactions = vec![Transaction::new("title", Value::from(title))];
+
+    let description = if matches.is_present("edit") {
+        Some(read_message(None)?)
+    } else {
+        match matches.value_of("description") {
+            Some(description) => Some(read_message(Some(description))?),
+            None => None,
+        }
+    };
+
+    if let Some(description) = description {
+        transactions.push(Transaction::new("description", Value::from(description)));
+    }
+
+    if let Some(priority) = matches.value_of("priority") {
+        let value = Priority::get_value_for_name(priority)?;
+        transactions.push(Transaction::new(
+            "priority",
+            Value::from(Priority::get_keyword_for_value(value)?),
+        ));
+    }
+
+    let mut runtime = Runtime::new()?;
+
+    if let Some(assignee) = matches.value_of("assign") {
+        let user = runtime.block_on(users::get_user(assignee, config))?;
+        transactions.push(Transaction::new("owner", Value::from(user.phid)));
+    }
+
+    if let Some(project_names) = matches.values_of("project") {
+        let mut phids = vec![];
+        for project in project_names {
+            let project = runtime.block_on(projects::get_project(project, config))?;
+            phids.push(Value::from(project.phid));
+        }
+        transactions.push(Transaction::new("projects.add", Value::Array(phids)));
+    }
+
+    if let Some(subscribers) = matches.values_of("subscriber") {
+        let mut phids = vec![];
+        for subscriber in subscribers {
+            let user = runtime.block_on(users::get_user(subscriber, config))?;
+            phids.push(Value::from(user.phid));
+        }
+        transactions.push(Transaction::new("subscribers.add", Value::Array(phids)));
+    }
+
+    if let Some(parent) = matches.value_of("parent") {
+        let parent = runtime.block_on(get_task(config, parse_object_id('T', parent)?))?;
+        transactions.push(Transaction::new("parent", Value::from(parent.phid)));
+    }
+
+    let result = runtime.block_on(edit_task_with_identifier(config, None, &transactions))?;
+
+    if format != Format::Table {
+        let record = runtime.block_on(get_task_record(config, result.object.id))?;
+        return output::print_record(format, &record);
+    }
+
+    println!(
+        "Created task {}",
+        Maniphest::get_url_for_id(config, result.object.id)
+    );
+    Ok(())
+}
+
 fn process_list_tasks(
     matches: &ArgMatches,
     config: &FabConfig,
     preferences: &Preferences,
 ) -> Result<(), Error> {
-    let pref_limit = preferences.default_limit_str.as_str();
-    let limit = matches.value_of("limit").unwrap_or(pref_limit);
+    let limit = parse_limit(matches)?;
+    let output = Output::from_matches(matches, preferences.default_columns.get("tasks"))?;
 
     let priorities: Vec<_> = matches.values_of("priority")
         .expect("Couldn't parse priority. Must be one of ['unbreak-now', 'needs-triage', 'high', 'normal', 'low', 'wishlist']")
         .collect();
 
-    let priorities: Vec<i32> = priorities
+    let mut priorities: Vec<i32> = priorities
         .iter()
         .map(|priority| Priority::get_value_for_name(priority).unwrap())
         .collect();
 
     let sort = matches.value_of("sort").unwrap();
 
-    let status = matches.value_of("status").unwrap();
+    let mut status = matches.value_of("status");
+
+    // A saved query brings its own filters, so the defaults only apply when given explicitly.
+    let query_key = matches.value_of("query-key");
+    if query_key.is_some() {
+        if matches.occurrences_of("priority") == 0 {
+            priorities.clear();
+        }
+        if matches.occurrences_of("status") == 0 {
+            status = None;
+        }
+    }
+
+    let mut runtime = Runtime::new()?;
+
+    let mut filter = TaskFilter {
+        query_key: query_key.map(str::to_string),
+        ..TaskFilter::default()
+    };
 
-    let tasks = tokio::runtime::Runtime::new()?.block_on(get_tasks(
+    if let Some(project_name) = matches.value_of("project") {
+        let project = runtime.block_on(projects::get_project(project_name, config))?;
+
+        if let Some(column_name) = matches.value_of("column") {
+            let column = runtime.block_on(projects::get_column(&project, column_name, config))?;
+            filter.column_phid = Some(column.phid);
+        }
+
+        filter.project_phid = Some(project.phid);
+    }
+
+    let tasks = runtime.block_on(get_tasks(
         limit,
         &priorities,
         &sort.to_string(),
-        &status,
+        status,
+        &filter,
         config,
     ))?;
-    render_tasks(&tasks, config);
-    Ok(())
-}
 
-#[derive(Debug, Deserialize)]
-struct ManiphestSearchData {
-    data: Vec<Maniphest>,
+    if output.quiet {
+        return finish_quietly(&tasks);
+    }
+
+    print_tasks(&output, &tasks, config)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Default)]
 pub struct Maniphest {
-    id: i32,
+    pub id: i32,
+    #[serde(default)]
+    phid: String,
     fields: Fields,
+    #[serde(default)]
+    attachments: Attachments,
 }
 
 impl Maniphest {
+    /// Whether the task is in the given column of the project's workboard. Only works
+    /// for tasks that were fetched with the `columns` attachment.
+    pub fn is_in_column(&self, project_phid: &str, column_phid: &str) -> bool {
+        match &self.attachments.columns {
+            Some(columns) => columns.column_phids(project_phid).contains(&column_phid),
+            None => false,
+        }
+    }
+
+    /// The priority, title and url of the task as cells of a table row.
+    pub fn get_cells(&self, config: &FabConfig) -> Vec<Cell> {
+        vec![
+            Cell::new(&self.fields.priority.name)
+                .bg(self.get_background())
+                .fg(self.get_foreground())
+                .set_alignment(CellAlignment::Center)
+                .add_attribute(Attribute::Bold),
+            Cell::new(&self.fields.name),
+            Cell::new(&self.get_task_url(config)).add_attribute(Attribute::Bold),
+        ]
+    }
+
+    /// A card for the task on a workboard, colored by its priority.
+    pub fn get_card(&self) -> Cell {
+        Cell::new(format!("T{} {}", self.id, self.fields.name))
+            .bg(self.get_background())
+            .fg(self.get_foreground())
+    }
+
     fn get_task_url(&self, config: &FabConfig) -> String {
-        return format!("{}T{}", &config.hosted_instance, &self.id);
+        Maniphest::get_url_for_id(config, self.id)
+    }
+
+    pub fn get_url_for_id(config: &FabConfig, id: i32) -> String {
+        format!("{}T{}", &config.hosted_instance, id)
     }
 
     fn get_background(&self) -> Color {
-        let priority = &self.fields.priority.value;
-        match priority {
-            100 => Color::Rgb {
-                r: 255,
-                g: 182,
-                b: 193,
-            },
-            90 => Color::Magenta,
-            80 => Color::DarkRed,
-            50 => Color::DarkYellow,
-            25 => Color::Yellow,
-            0 => Color::Cyan,
-            _ => Color::Blue,
-        }
+        priority_background(self.fields.priority.value)
     }
 
     fn get_foreground(&self) -> Color {
-        let priority = &self.fields.priority.value;
-        match priority {
-            100 => Color::Black,
-            90 => Color::White,
-            80 => Color::White,
-            50 => Color::Black,
-            25 => Color::Black,
-            0 => Color::Black,
-            _ => Color::White,
+        priority_foreground(self.fields.priority.value)
+    }
+}
+
+fn priority_background(priority: i32) -> Color {
+    match priority {
+        100 => Color::Rgb {
+            r: 255,
+            g: 182,
+            b: 193,
+        },
+        90 => Color::Magenta,
+        80 => Color::DarkRed,
+        50 => Color::DarkYellow,
+        25 => Color::Yellow,
+        0 => Color::Cyan,
+        _ => Color::Blue,
+    }
+}
+
+fn priority_foreground(priority: i32) -> Color {
+    match priority {
+        100 => Color::Black,
+        90 => Color::White,
+        80 => Color::White,
+        50 => Color::Black,
+        25 => Color::Black,
+        0 => Color::Black,
+        _ => Color::White,
+    }
+}
+
+/// A task as printed with `--format`. See `output::Record`.
+#[derive(Debug, Serialize)]
+pub struct TaskRecord {
+    pub id: i32,
+    pub phid: String,
+    pub title: String,
+    pub status: String,
+    pub priority: String,
+    pub priority_value: i32,
+    pub description: String,
+    pub author_phid: String,
+    pub owner_phid: Option<String>,
+    pub points: Option<Value>,
+    pub url: String,
+    pub date_created: i64,
+    pub date_modified: i64,
+    pub project_phids: Vec<String>,
+    pub subscriber_phids: Vec<String>,
+}
+
+impl TaskRecord {
+    pub fn new(task: &Maniphest, config: &FabConfig) -> TaskRecord {
+        TaskRecord {
+            id: task.id,
+            phid: task.phid.clone(),
+            title: task.fields.name.clone(),
+            status: task.fields.status.value.clone(),
+            priority: task.fields.priority.name.clone(),
+            priority_value: task.fields.priority.value,
+            description: task.fields.description.raw.clone(),
+            author_phid: task.fields.author_phid.clone(),
+            owner_phid: task.fields.owner_phid.clone(),
+            points: task.fields.points.clone(),
+            url: task.get_task_url(config),
+            date_created: task.fields.date_created,
+            date_modified: task.fields.date_modified,
+            project_phids: task
+                .attachments
+                .projects
+                .iter()
+                .flat_map(|projects| projects.project_phids.iter().cloned())
+                .collect(),
+            subscriber_phids: task
+                .attachments
+                .subscribers
+                .iter()
+                .flat_map(|subscribers| subscribers.subscriber_phids.iter().cloned())
+                .collect(),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl Record for TaskRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "phid",
+        "title",
+        "status",
+        "priority",
+        "priority_value",
+        "description",
+        "author_phid",
+        "owner_phid",
+        "points",
+        "url",
+        "date_created",
+        "date_modified",
+        "project_phids",
+        "subscriber_phids",
+    ];
+
+    fn cell(&self, field: &str, text: String) -> Cell {
+        match field {
+            "priority" => Cell::new(text)
+                .bg(priority_background(self.priority_value))
+                .fg(priority_foreground(self.priority_value))
+                .set_alignment(CellAlignment::Center)
+                .add_attribute(Attribute::Bold),
+            "url" => Cell::new(text).add_attribute(Attribute::Bold),
+            _ => Cell::new(text),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
 pub struct Fields {
     name: String,
     status: Status,
     priority: Priority,
+    #[serde(default)]
+    description: Description,
+    #[serde(default, rename = "authorPHID")]
+    author_phid: String,
+    #[serde(default, rename = "ownerPHID")]
+    owner_phid: Option<String>,
+    /// Points are only sent when they are enabled on the instance.
+    #[serde(default)]
+    points: Option<Value>,
+    #[serde(default, rename = "dateCreated")]
+    date_created: i64,
+    #[serde(default, rename = "dateModified")]
+    date_modified: i64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Description {
+    raw: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Attachments {
+    projects: Option<ProjectsAttachment>,
+    subscribers: Option<SubscribersAttachment>,
+    columns: Option<ColumnsAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColumnsAttachment {
+    /// Columns of the task keyed by the PHID of the board's project. Conduit sends
+    /// an empty list instead of a map when the task isn't on any board.
+    boards: Value,
+}
+
+impl ColumnsAttachment {
+    fn column_phids(&self, project_phid: &str) -> Vec<&str> {
+        self.boards
+            .get(project_phid)
+            .and_then(|board| board.get("columns"))
+            .and_then(|columns| columns.as_array())
+            .map(|columns| {
+                columns
+                    .iter()
+                    .filter_map(|column| column.get("phid").and_then(|phid| phid.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Deserialize)]
+pub struct ProjectsAttachment {
+    #[serde(rename = "projectPHIDs")]
+    project_phids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
 pub struct Status {
     name: String,
     value: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Default)]
 pub struct Priority {
     value: i32,
     name: String,
@@ -186,11 +902,48 @@ impl Priority {
             _ => Result::Err(anyhow!("Unknown value of priority")),
         }
     }
+
+    /// The keyword that `maniphest.edit` expects for a priority value.
+    pub fn get_keyword_for_value(value: i32) -> Result<&'static str, Error> {
+        match value {
+            100 => Result::Ok("unbreak"),
+            90 => Result::Ok("triage"),
+            80 => Result::Ok("high"),
+            50 => Result::Ok("normal"),
+            25 => Result::Ok("low"),
+            0 => Result::Ok("wish"),
+            _ => Result::Err(anyhow!("Unknown value of priority")),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fab::conduit::ConduitClient;
+
+    #[test]
+    fn test_task_record_fields() {
+        let config = FabConfig::new(
+            ConduitClient::new("https://phab.example.com/", ""),
+            String::new(),
+        );
+        let record = TaskRecord::new(&Maniphest::default(), &config);
+
+        let value = serde_json::to_value(&record).unwrap();
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        let mut fields = TaskRecord::FIELDS.to_vec();
+        fields.sort();
+
+        assert_eq!(fields, keys);
+        assert_eq!(record.url, "https://phab.example.com/T0");
+    }
 
     #[test]
     fn test_get_value_for_name() {
@@ -231,6 +984,83 @@ mod tests {
         );
     }
 
+    fn handle(phid: &str, full_name: &str, status: &str) -> Handle {
+        Handle {
+            phid: phid.to_string(),
+            uri: String::new(),
+            name: String::new(),
+            full_name: full_name.to_string(),
+            type_name: String::from("Task"),
+            status: status.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_get_tree_lines() {
+        let mut children = HashMap::new();
+        children.insert(
+            String::from("PHID-TASK-1"),
+            vec![String::from("PHID-TASK-2"), String::from("PHID-TASK-3")],
+        );
+        children.insert(
+            String::from("PHID-TASK-2"),
+            vec![String::from("PHID-TASK-4")],
+        );
+
+        let mut handles = HashMap::new();
+        handles.insert(
+            String::from("PHID-TASK-2"),
+            handle("PHID-TASK-2", "T2: Design", "open"),
+        );
+        handles.insert(
+            String::from("PHID-TASK-3"),
+            handle("PHID-TASK-3", "T3: Implement", "closed"),
+        );
+        handles.insert(
+            String::from("PHID-TASK-4"),
+            handle("PHID-TASK-4", "T4: Mockups", "open"),
+        );
+
+        assert_eq!(
+            vec!["T2: Design", "  T4: Mockups", "T3: Implement (Closed)"],
+            get_tree_lines("PHID-TASK-1", &children, &handles, 0)
+        );
+    }
+
+    #[test]
+    fn test_is_in_column() {
+        let mut maniphest = Maniphest {
+            id: 32,
+            ..Default::default()
+        };
+        assert!(!maniphest.is_in_column("PHID-PROJ-1", "PHID-PCOL-1"));
+
+        maniphest.attachments.columns = Some(ColumnsAttachment { boards: json!([]) });
+        assert!(!maniphest.is_in_column("PHID-PROJ-1", "PHID-PCOL-1"));
+
+        maniphest.attachments.columns = Some(ColumnsAttachment {
+            boards: json!({
+                "PHID-PROJ-1": {
+                    "columns": [{"id": 1, "phid": "PHID-PCOL-1", "name": "Backlog"}]
+                }
+            }),
+        });
+        assert!(maniphest.is_in_column("PHID-PROJ-1", "PHID-PCOL-1"));
+        assert!(!maniphest.is_in_column("PHID-PROJ-1", "PHID-PCOL-2"));
+        assert!(!maniphest.is_in_column("PHID-PROJ-2", "PHID-PCOL-1"));
+    }
+
+    #[test]
+    fn test_get_keyword_for_value() {
+        assert_eq!("unbreak", Priority::get_keyword_for_value(100).unwrap());
+        assert_eq!("triage", Priority::get_keyword_for_value(90).unwrap());
+        assert_eq!("high", Priority::get_keyword_for_value(80).unwrap());
+        assert_eq!("normal", Priority::get_keyword_for_value(50).unwrap());
+        assert_eq!("low", Priority::get_keyword_for_value(25).unwrap());
+        assert_eq!("wish", Priority::get_keyword_for_value(0).unwrap());
+        assert!(Priority::get_keyword_for_value(42).is_err());
+    }
+
     #[test]
     fn maniphest_get_colo_unbreak_now() {
         let maniphest = Maniphest {
@@ -245,7 +1075,9 @@ mod tests {
                     value: 100,
                     name: String::from("Unbreak Now"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(
@@ -273,7 +1105,9 @@ mod tests {
                     value: 80,
                     name: String::from("High"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::DarkRed, maniphest.get_background());
@@ -294,7 +1128,9 @@ mod tests {
                     value: 90,
                     name: String::from("Needs Triage"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Magenta, maniphest.get_background());
@@ -315,7 +1151,9 @@ mod tests {
                     value: 50,
                     name: String::from("Normal"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::DarkYellow, maniphest.get_background());
@@ -336,7 +1174,9 @@ mod tests {
                     value: 25,
                     name: String::from("Low"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Yellow, maniphest.get_background());
@@ -357,7 +1197,9 @@ mod tests {
                     value: 0,
                     name: String::from("Wishlist"),
                 },
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         assert_eq!(Color::Cyan, maniphest.get_background());
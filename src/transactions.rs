@@ -0,0 +1,59 @@
+use crate::diffs::{edit_revision, get_revision_record};
+use crate::errors::InvalidArgument;
+use crate::output::{self, Format};
+use crate::structs::FabConfig;
+use crate::tasks::{edit_task, get_task_record};
+use crate::utils::parse_object_id;
+use anyhow::Error;
+use fab::conduit::Transaction;
+
+/// Applies the transactions to a revision such as `D1234` or a task such as `T1234`
+/// and returns the url of the object.
+pub async fn edit_object(
+    config: &FabConfig,
+    object: &str,
+    transactions: &[Transaction],
+) -> Result<String, Error> {
+    let (kind, id) = parse_object(object)?;
+    if kind == 'D' {
+        edit_revision(config, id, transactions).await?;
+    } else {
+        edit_task(config, id, transactions).await?;
+    }
+    Ok(format!("{}{}{}", config.hosted_instance, kind, id))
+}
+
+/// Prints a revision or a task in the given format, e.g. after it was edited.
+pub async fn print_object(format: Format, config: &FabConfig, object: &str) -> Result<(), Error> {
+    match parse_object(object)? {
+        ('D', id) => output::print_record(format, &get_revision_record(config, id).await?),
+        (_, id) => output::print_record(format, &get_task_record(config, id).await?),
+    }
+}
+
+/// Splits an object such as `D1234` or `t12` into its kind, `D` or `T`, and its id.
+fn parse_object(object: &str) -> Result<(char, i32), Error> {
+    let object = object.trim();
+
+    match object.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some(kind) if kind == 'D' || kind == 'T' => Ok((kind, parse_object_id(kind, object)?)),
+        _ => Err(InvalidArgument(format!(
+            "'{}' is neither a revision nor a task. Example: D1234 or T1234",
+            object
+        ))
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object() {
+        assert_eq!(('D', 1234), parse_object("D1234").unwrap());
+        assert_eq!(('T', 12), parse_object(" t12 ").unwrap());
+        assert!(parse_object("P12").is_err());
+        assert!(parse_object("").is_err());
+    }
+}
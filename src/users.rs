@@ -1,40 +1,25 @@
-use crate::auth;
+use crate::errors::NotFound;
 use crate::structs::FabConfig;
 use anyhow::Error;
+use fab::conduit::{Limit, Search};
 use serde::{Deserialize, Serialize};
-use serde_json::{Map, Value};
 
-pub async fn get_user(username: &str, config: &FabConfig) -> Result<User, Error> {
-    let mut map = Map::new();
-    map.insert(
-        "api.token".to_string(),
-        Value::from(config.api_token.clone()),
-    );
-    map.insert(
-        "constraints[usernames][0]".to_string(),
-        Value::from(username),
-    );
-
-    let url = format!("{}{}", &config.hosted_instance, "api/user.search");
+const USER_SEARCH: &str = "user.search";
 
-    let json_body = Value::Object(map);
+pub async fn get_user(username: &str, config: &FabConfig) -> Result<User, Error> {
+    let search = Search::new(USER_SEARCH).constraints("usernames", &[username]);
 
-    let result =
-        auth::send::<UserSearchData>(config, reqwest::Client::new().post(&url).form(&json_body))
-            .await?
-            .data
-            .into_iter()
-            .next()
-            .unwrap();
+    let result = config
+        .client()
+        .search::<User>(&search, Limit::Count(1))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| NotFound(format!("Could not find user {}", username)))?;
 
     Ok(result)
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-struct UserSearchData {
-    data: Vec<User>,
-}
-
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct User {
     pub id: i32,
@@ -0,0 +1,143 @@
+use crate::errors::InvalidArgument;
+use anyhow::{anyhow, Error};
+use clap::ArgMatches;
+use dialoguer::Editor;
+use fab::conduit::Limit;
+use std::io;
+use std::io::Read;
+use std::process::Command;
+
+/// Parses an object identifier such as `D1234` or `T12` into its numeric id.
+/// The prefix is optional so that `1234` is accepted as well.
+pub fn parse_object_id(prefix: char, input: &str) -> Result<i32, Error> {
+    let input = input.trim();
+    let id = input
+        .strip_prefix(prefix)
+        .or_else(|| input.strip_prefix(prefix.to_ascii_lowercase()))
+        .unwrap_or(input);
+
+    id.parse::<i32>().map_err(|_| {
+        InvalidArgument(format!("'{}' is not a valid {}-prefixed id", input, prefix)).into()
+    })
+}
+
+/// Reads the `--limit` and `--all` arguments of a command.
+pub fn parse_limit(matches: &ArgMatches) -> Result<Limit, Error> {
+    if matches.is_present("all") {
+        return Ok(Limit::All);
+    }
+
+    let limit = matches
+        .value_of("limit")
+        .ok_or_else(|| anyhow!("No limit specified"))?;
+
+    parse_limit_value(limit)
+}
+
+/// Parses a limit of at least one result, e.g. from `--limit` or the preferences.
+pub fn parse_limit_value(limit: &str) -> Result<Limit, Error> {
+    match limit.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(Limit::Count(count)),
+        _ => Err(InvalidArgument(format!(
+            "Invalid limit {}. Must be a positive number",
+            limit
+        ))
+        .into()),
+    }
+}
+
+/// Reads a message given on the command line. A message of `-` is read from stdin
+/// and a missing message opens up `$EDITOR`.
+pub fn read_message(message: Option<&str>) -> Result<String, Error> {
+    let message = match message {
+        Some("-") => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        }
+        Some(message) => message.to_string(),
+        None => Editor::new().edit("")?.unwrap_or_default(),
+    };
+
+    let message = message.trim().to_string();
+
+    if message.is_empty() {
+        return Err(anyhow!("Aborting because the message is empty"));
+    }
+
+    Ok(message)
+}
+
+/// Opens the url in the default browser.
+pub fn open_in_browser(url: &str) -> Result<(), Error> {
+    let status = if cfg!(target_os = "macos") {
+        Command::new("open").arg(url).status()
+    } else if cfg!(target_os = "windows") {
+        Command::new("cmd").args(&["/C", "start", url]).status()
+    } else {
+        Command::new("xdg-open").arg(url).status()
+    };
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(anyhow!("Couldn't open {} in the browser", url)),
+    }
+}
+
+/// Formats a unix timestamp returned by Conduit as a human readable UTC date.
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+
+    // Convert days since the epoch into a civil date.
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds % 3600) / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object_id() {
+        assert_eq!(1234, parse_object_id('D', "D1234").unwrap());
+        assert_eq!(1234, parse_object_id('D', "d1234").unwrap());
+        assert_eq!(1234, parse_object_id('D', "1234").unwrap());
+        assert_eq!(12, parse_object_id('T', "T12").unwrap());
+        assert!(parse_object_id('D', "T12").is_err());
+        assert!(parse_object_id('D', "D").is_err());
+    }
+
+    #[test]
+    fn test_parse_limit_value() {
+        assert_eq!(Limit::Count(20), parse_limit_value("20").unwrap());
+        assert!(parse_limit_value("0").is_err());
+        assert!(parse_limit_value("-5").is_err());
+        assert!(parse_limit_value("ten").is_err());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!("1970-01-01 00:00 UTC", format_timestamp(0));
+        assert_eq!("2020-04-12 16:26 UTC", format_timestamp(1_586_708_760));
+        assert_eq!("2000-02-29 23:59 UTC", format_timestamp(951_868_799));
+    }
+}